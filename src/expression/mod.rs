@@ -13,11 +13,9 @@
 //#[macro_use]
 extern crate term;
 use std::fmt;
-use std::process;
-
-use errors::{ColorConfig, Handler};
-use syntax::codemap::CodeMap;
-use std::rc::Rc;
+use std::collections::HashMap;
+use std::cell::Cell;
+use std::cmp;
 
 #[derive(Clone, PartialEq)]
 pub struct BinaryExpressionData {
@@ -40,6 +38,12 @@ pub enum Expression {
     BinaryExpression(BinaryExpressionData),
     // A sub-expression acted upon by an operator
     UnaryExpression(UnaryExpressionData),
+    // A sub-expression converted to another integer type, as with Rust's `as`
+    CastExpression(CastExpressionData),
+    // An element of an array or slice
+    ArrayIndex(ArrayIndexData),
+    // An array or slice with one element replaced; only produced by assignments to elements
+    ArrayStore(ArrayStoreData),
     // A variable; should be either one of a function's formal arguments,
     // a special "return" variable, or something from an encapsulating scope.
     VariableMapping(VariableMappingData),
@@ -47,7 +51,16 @@ pub enum Expression {
     BooleanLiteral(bool),
     // Integer literals
     UnsignedBitVector(UnsignedBitVectorData),
-    SignedBitVector(SignedBitVectorData)
+    SignedBitVector(SignedBitVectorData),
+    // A test of which variant an enum value holds; replaced by a comparison of the value's
+    // discriminant (see `resolve_variant_checks`) before it reaches the weakest precondition
+    VariantCheck(VariantCheckData),
+    // A sub-expression in which a variable is bound by a quantifier
+    QuantifiedExpression(QuantifiedExpressionData),
+    // A sub-expression evaluated on entry to the function; replaced by the sub-expression over
+    // snapshots of the entry values (see `resolve_old_expressions`) before it reaches the weakest
+    // precondition
+    Old(Box<Expression>)
 }
 
 // Used for representing Expression types as strings, recursively.
@@ -58,14 +71,29 @@ impl fmt::Display for Expression {
                 write!(f, "({} {} {})", *b.left, b.op, *b.right)
             },
             Expression::UnaryExpression (ref u) => write!(f, "({} {})", u.op, *u.e),
+            Expression::CastExpression (ref c) => write!(f, "({} as {})", *c.e, c.var_type),
+            Expression::ArrayIndex (ref a) => write!(f, "{}[{}]", *a.array, *a.index),
+            Expression::ArrayStore (ref a) => {
+                write!(f, "({} with [{}] = {})", *a.array, *a.index, *a.value)
+            },
             Expression::VariableMapping (ref v) => write!(f, "({} : {})", v.name, v.var_type),
             Expression::BooleanLiteral (ref b) => write!(f, "({})", b),
             Expression::UnsignedBitVector(ref u) => {
-                write!(f, "({} : u{})", u.value, u.size.to_string())
+                write!(f, "({} : u{})", format_wide(u.high, u.value), u.size.to_string())
+            },
+            Expression::SignedBitVector(ref s) if s.size > 64 => {
+                write!(f, "({} : i{})", format_signed_wide(s.high, s.value), s.size.to_string())
             },
             Expression::SignedBitVector(ref s) => {
                 write!(f, "({} : i{})", s.value, s.size.to_string())
-            }
+            },
+            Expression::VariantCheck(ref v) => {
+                write!(f, "({} : {} is {})", v.var.name, v.var.var_type, v.variant)
+            },
+            Expression::QuantifiedExpression(ref q) => {
+                write!(f, "({} {}, {})", q.quantifier, q.var, *q.e)
+            },
+            Expression::Old(ref e) => write!(f, "(old {})", **e)
         }
     }
 }
@@ -96,16 +124,68 @@ impl fmt::Display for VariableMappingData {
     }
 }
 
+// Literals wider than 64 bits are stored in two halves, `high` holding the upper 64 bits and
+// `value` the lower 64 bits. For narrower literals `high` is 0 and `value` is the whole value.
 #[derive(Clone, PartialEq)]
 pub struct UnsignedBitVectorData {
     pub size: u8,
     pub value: u64,
+    pub high: u64,
 }
 
 #[derive(Clone, PartialEq)]
 pub struct SignedBitVectorData {
     pub size: u8,
     pub value: i64,
+    pub high: i64,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct VariantCheckData {
+    pub var: VariableMappingData,
+    pub variant: String,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct CastExpressionData {
+    pub e: Box<Expression>,
+    // The type the sub-expression is converted to
+    pub var_type: String
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ArrayIndexData {
+    pub array: Box<Expression>,
+    pub index: Box<Expression>
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ArrayStoreData {
+    pub array: Box<Expression>,
+    pub index: Box<Expression>,
+    pub value: Box<Expression>
+}
+
+#[derive(Clone, PartialEq)]
+pub struct QuantifiedExpressionData {
+    pub quantifier: Quantifier,
+    pub var: VariableMappingData,
+    pub e: Box<Expression>
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Quantifier {
+    ForAll,
+    Exists,
+}
+
+impl fmt::Display for Quantifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Quantifier::ForAll => write!(f, "FORALL"),
+            Quantifier::Exists => write!(f, "EXISTS"),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -204,6 +284,9 @@ impl fmt::Display for UnaryOperator {
 /// * `replacement` - The Expression to replace the target, if found.
 ///
 /// # Remarks:
+/// * Substitution is capture-avoiding: a quantifier that binds a variable named like the target
+///   is left alone, and a quantifier that binds a variable named like one in the replacement has
+///   its variable renamed first.
 ///
 pub fn substitute_variable_with_expression (source_expression: &mut Expression,
                                             target: &VariableMappingData,
@@ -219,6 +302,45 @@ pub fn substitute_variable_with_expression (source_expression: &mut Expression,
             // Recurisvely call the sub-expression
             substitute_variable_with_expression(&mut(*u.e), target, replacement);
         },
+        &mut Expression::CastExpression(ref mut c) => {
+            // Recurisvely call the sub-expression
+            substitute_variable_with_expression(&mut(*c.e), target, replacement);
+        },
+        &mut Expression::ArrayIndex(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            substitute_variable_with_expression(&mut(*a.array), target, replacement);
+            substitute_variable_with_expression(&mut(*a.index), target, replacement);
+        },
+        &mut Expression::ArrayStore(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            substitute_variable_with_expression(&mut(*a.array), target, replacement);
+            substitute_variable_with_expression(&mut(*a.index), target, replacement);
+            substitute_variable_with_expression(&mut(*a.value), target, replacement);
+        },
+        &mut Expression::Old(ref mut e) => {
+            // Recurisvely call the sub-expression
+            substitute_variable_with_expression(&mut(**e), target, replacement);
+        },
+        &mut Expression::QuantifiedExpression(ref mut q) => {
+            // The target is not free in the sub-expression if the quantifier binds its name
+            if q.var.name != target.name {
+                // Rename the bound variable if the replacement would be captured by it
+                let mut free = Vec::new();
+                collect_variables(replacement, &mut free);
+                if free.iter().any(|v| v.name == q.var.name) {
+                    let mut fresh = q.var.clone();
+                    while contains_name(&q.e, &fresh.name)
+                          || free.iter().any(|v| v.name == fresh.name) {
+                        fresh.name.push('\'');
+                    }
+                    let renamed = Expression::VariableMapping(fresh.clone());
+                    substitute_variable_with_expression(&mut(*q.e), &q.var, &renamed);
+                    q.var = fresh;
+                }
+                // Recurisvely call the sub-expression
+                substitute_variable_with_expression(&mut(*q.e), target, replacement);
+            }
+        },
         &mut Expression::VariableMapping(ref mut v) => {
             // Substitute the variable if it matches the target
             if v == target {
@@ -236,6 +358,575 @@ pub fn substitute_variable_with_expression (source_expression: &mut Expression,
     }
 }
 
+/// Returns the variable holding the discriminant of an enum value.
+///
+/// # Arguments:
+/// * `var` - The enum value.
+///
+/// # Return:
+/// * A `u64` variable named after `var`, whose value is the index of the variant `var` holds.
+///
+/// # Remarks:
+/// * The fields of each variant are modeled as variables named `<var>.<variant>.<field>`.
+///
+pub fn discriminant(var: &VariableMappingData) -> VariableMappingData {
+    VariableMappingData {
+        name: var.name.clone() + ".discr",
+        var_type: "u64".to_string()
+    }
+}
+
+/// Recurses through an Expression and replaces every variant check with a comparison of the
+/// checked value's discriminant against the index of the variant.
+///
+/// # Arguments:
+/// * `source_expression` - The Expression to be recursed through.
+/// * `enum_variants` - The variant names of each enum type, in declaration order.
+///
+/// # Return:
+/// * Ok(true) if all variant checks were resolved.
+/// * Err(String) otherwise, the String containing a message about the first unknown variant.
+///
+/// # Remarks:
+///
+pub fn resolve_variant_checks (source_expression: &mut Expression,
+                               enum_variants: &HashMap<String, Vec<String>>)
+                               -> Result<bool, String> {
+    let resolved: Expression;
+    match source_expression {
+        &mut Expression::BinaryExpression(ref mut b) => {
+            // Recurisvely call the sub-expressions
+            try!(resolve_variant_checks(&mut(*b.left), enum_variants));
+            return resolve_variant_checks(&mut(*b.right), enum_variants);
+        },
+        &mut Expression::UnaryExpression(ref mut u) => {
+            // Recurisvely call the sub-expression
+            return resolve_variant_checks(&mut(*u.e), enum_variants);
+        },
+        &mut Expression::QuantifiedExpression(ref mut q) => {
+            // Recurisvely call the sub-expression
+            return resolve_variant_checks(&mut(*q.e), enum_variants);
+        },
+        &mut Expression::CastExpression(ref mut c) => {
+            // Recurisvely call the sub-expression
+            return resolve_variant_checks(&mut(*c.e), enum_variants);
+        },
+        &mut Expression::ArrayIndex(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            try!(resolve_variant_checks(&mut(*a.array), enum_variants));
+            return resolve_variant_checks(&mut(*a.index), enum_variants);
+        },
+        &mut Expression::ArrayStore(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            try!(resolve_variant_checks(&mut(*a.array), enum_variants));
+            try!(resolve_variant_checks(&mut(*a.index), enum_variants));
+            return resolve_variant_checks(&mut(*a.value), enum_variants);
+        },
+        &mut Expression::Old(ref mut e) => {
+            // Recurisvely call the sub-expression
+            return resolve_variant_checks(&mut(**e), enum_variants);
+        },
+        &mut Expression::VariantCheck(ref v) => {
+            // Find the index of the variant within its enum
+            let index = match enum_variants.get(&v.var.var_type) {
+                Some(variants) => variants.iter().position(|name| *name == v.variant),
+                None => return Err(format!("Unknown enum type: \"{}\"", v.var.var_type)),
+            };
+            let index = match index {
+                Some(index) => index,
+                None => {
+                    return Err(format!("Unknown variant of {}: \"{}\"", v.var.var_type, v.variant));
+                }
+            };
+            resolved = Expression::BinaryExpression( BinaryExpressionData {
+                op: BinaryOperator::Equal,
+                left: Box::new(Expression::VariableMapping(discriminant(&v.var))),
+                right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
+                    size: 64,
+                    value: index as u64,
+                    high: 0
+                } ))
+            } );
+        },
+        _ => {
+            // Nothing to resolve
+            return Ok(true);
+        }
+    }
+
+    // Replace the variant check after the match to avoid scope issues
+    *source_expression = resolved;
+    Ok(true)
+}
+
+/// Recurses through an Expression and renames every field of an aggregate variable, i.e. every
+/// variable named `<target>.<..>` becomes `<replacement>.<..>`.
+///
+/// # Arguments:
+/// * `source_expression` - The Expression to be recursed through.
+/// * `target` - The name of the aggregate variable being replaced.
+/// * `replacement` - The name of the aggregate variable to replace it with.
+///
+/// # Remarks:
+/// * Used when an enum (or other aggregate) value is copied as a whole.
+///
+pub fn substitute_variable_fields (source_expression: &mut Expression,
+                                   target: &str,
+                                   replacement: &str) {
+    match source_expression {
+        &mut Expression::BinaryExpression(ref mut b) => {
+            // Recurisvely call the sub-expressions
+            substitute_variable_fields(&mut(*b.left), target, replacement);
+            substitute_variable_fields(&mut(*b.right), target, replacement);
+        },
+        &mut Expression::UnaryExpression(ref mut u) => {
+            // Recurisvely call the sub-expression
+            substitute_variable_fields(&mut(*u.e), target, replacement);
+        },
+        &mut Expression::QuantifiedExpression(ref mut q) => {
+            // Bound variables are primitive, so they have no fields to capture
+            substitute_variable_fields(&mut(*q.e), target, replacement);
+        },
+        &mut Expression::CastExpression(ref mut c) => {
+            // Recurisvely call the sub-expression
+            substitute_variable_fields(&mut(*c.e), target, replacement);
+        },
+        &mut Expression::ArrayIndex(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            substitute_variable_fields(&mut(*a.array), target, replacement);
+            substitute_variable_fields(&mut(*a.index), target, replacement);
+        },
+        &mut Expression::ArrayStore(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            substitute_variable_fields(&mut(*a.array), target, replacement);
+            substitute_variable_fields(&mut(*a.index), target, replacement);
+            substitute_variable_fields(&mut(*a.value), target, replacement);
+        },
+        &mut Expression::Old(ref mut e) => {
+            // Recurisvely call the sub-expression
+            substitute_variable_fields(&mut(**e), target, replacement);
+        },
+        &mut Expression::VariableMapping(ref mut v) => {
+            // Rename the variable if it is a field of the target
+            let prefix = target.to_string() + ".";
+            if v.name.starts_with(prefix.as_str()) {
+                v.name = replacement.to_string() + "." + &v.name[prefix.len()..];
+            }
+        },
+        _ => {
+            // No substitution should be done
+        }
+    }
+}
+
+/// Recurses through an Expression and collects the free variables it contains.
+///
+/// # Arguments:
+/// * `expression` - The Expression to be recursed through.
+/// * `variables` - The variables found so far. Each variable is added once, in the order it is
+///                 first found.
+///
+/// # Remarks:
+/// * Variables bound by a quantifier are not collected.
+///
+pub fn collect_variables (expression: &Expression, variables: &mut Vec<VariableMappingData>) {
+    match *expression {
+        Expression::BinaryExpression(ref b) => {
+            // Recurisvely call the sub-expressions
+            collect_variables(&b.left, variables);
+            collect_variables(&b.right, variables);
+        },
+        Expression::UnaryExpression(ref u) => {
+            // Recurisvely call the sub-expression
+            collect_variables(&u.e, variables);
+        },
+        Expression::CastExpression(ref c) => {
+            // Recurisvely call the sub-expression
+            collect_variables(&c.e, variables);
+        },
+        Expression::ArrayIndex(ref a) => {
+            // Recurisvely call the sub-expressions
+            collect_variables(&a.array, variables);
+            collect_variables(&a.index, variables);
+        },
+        Expression::ArrayStore(ref a) => {
+            // Recurisvely call the sub-expressions
+            collect_variables(&a.array, variables);
+            collect_variables(&a.index, variables);
+            collect_variables(&a.value, variables);
+        },
+        Expression::Old(ref e) => {
+            // Recurisvely call the sub-expression
+            collect_variables(e, variables);
+        },
+        Expression::QuantifiedExpression(ref q) => {
+            // Keep the variables that are not bound by the quantifier
+            let mut body_variables = Vec::new();
+            collect_variables(&q.e, &mut body_variables);
+            for v in body_variables {
+                if v.name != q.var.name && !variables.contains(&v) {
+                    variables.push(v);
+                }
+            }
+        },
+        Expression::VariableMapping(ref v) => {
+            if !variables.contains(v) {
+                variables.push(v.clone());
+            }
+        },
+        _ => {
+            // No variables to collect
+        }
+    }
+}
+
+/// Checks whether an Expression contains a quantifier.
+///
+/// # Arguments:
+/// * `expression` - The Expression to be recursed through.
+///
+pub fn contains_quantifier (expression: &Expression) -> bool {
+    match *expression {
+        Expression::BinaryExpression(ref b) => {
+            contains_quantifier(&b.left) || contains_quantifier(&b.right)
+        },
+        Expression::UnaryExpression(ref u) => contains_quantifier(&u.e),
+        Expression::CastExpression(ref c) => contains_quantifier(&c.e),
+        Expression::ArrayIndex(ref a) => contains_quantifier(&a.array) || contains_quantifier(&a.index),
+        Expression::ArrayStore(ref a) => {
+            contains_quantifier(&a.array)
+            || contains_quantifier(&a.index)
+            || contains_quantifier(&a.value)
+        },
+        Expression::Old(ref e) => contains_quantifier(e),
+        Expression::QuantifiedExpression(_) => true,
+        _ => false,
+    }
+}
+
+/// The suffix of the variables that hold the values of variables on entry to the function.
+pub const OLD_SUFFIX: &'static str = "@old";
+
+/// Recurses through an Expression and replaces every `old(e)` with `e`, renaming the variables of
+/// `e` to their snapshots.
+///
+/// # Arguments:
+/// * `source_expression` - The Expression to be recursed through.
+/// * `suffix` - Appended to the name of each variable inside `old(..)`, i.e. `OLD_SUFFIX` for the
+///              snapshot of its entry value, or `""` where the current value is the entry value.
+///
+/// # Return:
+/// * Ok(true) if all `old(..)` expressions were resolved.
+/// * Err(String) if `return` is used inside `old(..)`.
+///
+/// # Remarks:
+/// * Snapshots are free variables. `restore_old_values` equates them with the entry values once
+///   the weakest precondition reaches the start of the function.
+///
+pub fn resolve_old_expressions (source_expression: &mut Expression,
+                                suffix: &str) -> Result<bool, String> {
+    let resolved: Expression;
+    match source_expression {
+        &mut Expression::BinaryExpression(ref mut b) => {
+            // Recurisvely call the sub-expressions
+            try!(resolve_old_expressions(&mut(*b.left), suffix));
+            return resolve_old_expressions(&mut(*b.right), suffix);
+        },
+        &mut Expression::UnaryExpression(ref mut u) => {
+            // Recurisvely call the sub-expression
+            return resolve_old_expressions(&mut(*u.e), suffix);
+        },
+        &mut Expression::CastExpression(ref mut c) => {
+            // Recurisvely call the sub-expression
+            return resolve_old_expressions(&mut(*c.e), suffix);
+        },
+        &mut Expression::ArrayIndex(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            try!(resolve_old_expressions(&mut(*a.array), suffix));
+            return resolve_old_expressions(&mut(*a.index), suffix);
+        },
+        &mut Expression::ArrayStore(ref mut a) => {
+            // Recurisvely call the sub-expressions
+            try!(resolve_old_expressions(&mut(*a.array), suffix));
+            try!(resolve_old_expressions(&mut(*a.index), suffix));
+            return resolve_old_expressions(&mut(*a.value), suffix);
+        },
+        &mut Expression::QuantifiedExpression(ref mut q) => {
+            // Recurisvely call the sub-expression
+            return resolve_old_expressions(&mut(*q.e), suffix);
+        },
+        &mut Expression::Old(ref e) => {
+            let mut snapshot = (**e).clone();
+            // Nested old(..) expressions refer to the same entry values
+            try!(resolve_old_expressions(&mut snapshot, ""));
+            let mut variables = Vec::new();
+            collect_variables(&snapshot, &mut variables);
+            for v in variables {
+                if v.name == "return" || v.name.starts_with("return.") {
+                    return Err(format!("Invalid use of return value in old expression {}", **e));
+                }
+                let renamed = VariableMappingData {
+                    name: v.name.clone() + suffix,
+                    var_type: v.var_type.clone()
+                };
+                substitute_variable_with_expression(&mut snapshot,
+                                                    &v,
+                                                    &Expression::VariableMapping(renamed));
+            }
+            resolved = snapshot;
+        },
+        _ => {
+            // Nothing to resolve
+            return Ok(true);
+        }
+    }
+
+    // Replace the old expression after the match to avoid scope issues
+    *source_expression = resolved;
+    Ok(true)
+}
+
+/// Replaces every snapshot variable made by `resolve_old_expressions` with the variable it is a
+/// snapshot of.
+///
+/// # Arguments:
+/// * `source_expression` - A weakest precondition at the start of the function.
+///
+pub fn restore_old_values (source_expression: &mut Expression) {
+    let mut variables = Vec::new();
+    collect_variables(source_expression, &mut variables);
+    for v in variables {
+        if v.name.ends_with(OLD_SUFFIX) {
+            let entry = VariableMappingData {
+                name: v.name[..v.name.len() - OLD_SUFFIX.len()].to_string(),
+                var_type: v.var_type.clone()
+            };
+            substitute_variable_with_expression(source_expression,
+                                                &v,
+                                                &Expression::VariableMapping(entry));
+        }
+    }
+}
+
+// Checks whether a variable, free or bound, of the given name occurs in an Expression
+fn contains_name (expression: &Expression, name: &str) -> bool {
+    match *expression {
+        Expression::BinaryExpression(ref b) => {
+            contains_name(&b.left, name) || contains_name(&b.right, name)
+        },
+        Expression::UnaryExpression(ref u) => contains_name(&u.e, name),
+        Expression::CastExpression(ref c) => contains_name(&c.e, name),
+        Expression::ArrayIndex(ref a) => contains_name(&a.array, name) || contains_name(&a.index, name),
+        Expression::ArrayStore(ref a) => {
+            contains_name(&a.array, name)
+            || contains_name(&a.index, name)
+            || contains_name(&a.value, name)
+        },
+        Expression::Old(ref e) => contains_name(e, name),
+        Expression::QuantifiedExpression(ref q) => q.var.name == name || contains_name(&q.e, name),
+        Expression::VariableMapping(ref v) => v.name == name,
+        _ => false,
+    }
+}
+
+// Checks that every use of a bound variable has the type it is bound with
+fn check_bound_variable (expression: &Expression,
+                         var: &VariableMappingData) -> Result<bool, String> {
+    match *expression {
+        Expression::BinaryExpression(ref b) => {
+            try!(check_bound_variable(&b.left, var));
+            check_bound_variable(&b.right, var)
+        },
+        Expression::UnaryExpression(ref u) => check_bound_variable(&u.e, var),
+        Expression::CastExpression(ref c) => check_bound_variable(&c.e, var),
+        Expression::ArrayIndex(ref a) => {
+            try!(check_bound_variable(&a.array, var));
+            check_bound_variable(&a.index, var)
+        },
+        Expression::ArrayStore(ref a) => {
+            try!(check_bound_variable(&a.array, var));
+            try!(check_bound_variable(&a.index, var));
+            check_bound_variable(&a.value, var)
+        },
+        Expression::Old(ref e) => check_bound_variable(e, var),
+        // An inner quantifier binding the same name shadows the variable
+        Expression::QuantifiedExpression(ref q) if q.var.name != var.name => {
+            check_bound_variable(&q.e, var)
+        },
+        Expression::VariableMapping(ref v) if v.name == var.name && v.var_type != var.var_type => {
+            Err(format!("Quantified variable {} is used as {}", var, v))
+        },
+        _ => Ok(true),
+    }
+}
+
+// The width of `isize` and `usize` in bits. It is kept per thread, as the MIR pass parses all
+// conditions on the thread it sets the width on, so tests can each use their own width.
+thread_local!(static POINTER_WIDTH: Cell<u8> = Cell::new(64));
+
+/// Sets the width of `isize` and `usize` in bits on the current thread.
+///
+/// # Arguments:
+/// * `width` - The pointer width, either 32 or 64.
+///
+/// # Remarks:
+/// * The plugin sets this from the target being compiled for, or from its `pointer_width`
+///   argument, before any condition is parsed.
+///
+pub fn set_pointer_width(width: u8) {
+    POINTER_WIDTH.with(|w| w.set(width));
+}
+
+/// Returns the width of `isize` and `usize` in bits, which is 64 unless it has been set.
+pub fn pointer_width() -> u8 {
+    POINTER_WIDTH.with(|w| w.get())
+}
+
+/// Parses a `usize` literal, or returns `None` if it does not fit in the pointer width.
+pub fn parse_usize(digits: &str) -> Option<u64> {
+    let width = pointer_width();
+    match digits.parse::<u64>() {
+        Ok(value) if width >= 64 || value >> width == 0 => Some(value),
+        _ => None,
+    }
+}
+
+/// Parses an `isize` literal, which may start with a minus sign, or returns `None` if it does not
+/// fit in the pointer width.
+pub fn parse_isize(digits: &str) -> Option<i64> {
+    let width = pointer_width();
+    match digits.parse::<i64>() {
+        Ok(value) if width >= 64 || value >> (width - 1) == 0 || value >> (width - 1) == -1 => {
+            Some(value)
+        },
+        _ => None,
+    }
+}
+
+/// Parses the digits of an unsigned integer of up to 128 bits into its upper and lower 64 bits.
+///
+/// # Arguments:
+/// * `digits` - The digits of the integer, without a sign or prefix.
+/// * `radix` - The base of the digits, from 2 to 16.
+///
+/// # Return:
+/// * The upper and lower halves of the integer, or `None` if it is not a number or does not fit
+///   in 128 bits.
+///
+/// # Remarks:
+/// * The toolchain has no 128-bit integer types, so the value is built in 32-bit limbs.
+///
+pub fn parse_wide(digits: &str, radix: u32) -> Option<(u64, u64)> {
+    if digits.is_empty() {
+        return None;
+    }
+    // Least significant limb first
+    let mut limbs = [0u64; 4];
+    for c in digits.chars() {
+        let mut carry = match c.to_digit(radix) {
+            Some(digit) => digit as u64,
+            None => return None,
+        };
+        for limb in limbs.iter_mut() {
+            let x = *limb * radix as u64 + carry;
+            *limb = x & 0xffff_ffff;
+            carry = x >> 32;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(((limbs[3] << 32) | limbs[2], (limbs[1] << 32) | limbs[0]))
+}
+
+/// Formats an unsigned integer of up to 128 bits, given as its upper and lower 64 bits, in
+/// decimal.
+pub fn format_wide(high: u64, low: u64) -> String {
+    if high == 0 {
+        return low.to_string();
+    }
+    // Most significant limb first
+    let mut limbs = [high >> 32, high & 0xffff_ffff, low >> 32, low & 0xffff_ffff];
+    let mut digits = Vec::new();
+    while limbs.iter().any(|&limb| limb != 0) {
+        let mut remainder = 0;
+        for limb in limbs.iter_mut() {
+            let x = (remainder << 32) | *limb;
+            *limb = x / 10;
+            remainder = x % 10;
+        }
+        digits.push((b'0' + remainder as u8) as char);
+    }
+    digits.iter().rev().cloned().collect()
+}
+
+/// Formats a signed 128-bit integer, given as its upper and lower 64 bits, in decimal.
+pub fn format_signed_wide(high: i64, low: i64) -> String {
+    if high < 0 {
+        let (high, low) = negate_wide(high as u64, low as u64);
+        format!("-{}", format_wide(high, low))
+    } else {
+        format_wide(high as u64, low as u64)
+    }
+}
+
+/// Returns the two's complement negation of a 128-bit integer given as its upper and lower 64
+/// bits.
+pub fn negate_wide(high: u64, low: u64) -> (u64, u64) {
+    let low = (!low).wrapping_add(1);
+    let high = (!high).wrapping_add(if low == 0 { 1 } else { 0 });
+    (high, low)
+}
+
+/// Parses a `u128` literal into its upper and lower 64 bits, or `None` if it is out of range.
+pub fn parse_u128(digits: &str) -> Option<(u64, u64)> {
+    parse_wide(digits, 10)
+}
+
+/// Parses an `i128` literal, which may start with a minus sign, into its upper and lower 64 bits,
+/// or `None` if it is out of range.
+pub fn parse_i128(digits: &str) -> Option<(i64, i64)> {
+    let (negative, digits) = if digits.starts_with('-') {
+        (true, &digits[1..])
+    } else {
+        (false, digits)
+    };
+    let (high, low) = match parse_wide(digits, 10) {
+        Some(magnitude) => magnitude,
+        None => return None,
+    };
+    if negative {
+        let (high, low) = negate_wide(high, low);
+        // Anything down to -2^127 negates to a negative value, or to 0
+        if (high as i64) < 0 || (high == 0 && low == 0) {
+            return Some((high as i64, low as i64));
+        }
+    } else if (high as i64) >= 0 {
+        return Some((high as i64, low as i64));
+    }
+    None
+}
+
+/// Types a literal of the pointer width as an `isize` or `usize` (ie: `5usize`).
+///
+/// # Arguments:
+/// * `literal` - A bitvector literal whose size is the pointer width.
+/// * `var_type` - Either `"isize"` or `"usize"`.
+///
+/// # Return:
+/// * The literal cast to `var_type`.
+///
+/// # Remarks:
+/// * Literals are typed by their size, so a pointer-sized literal would otherwise be an `i64` or
+///   `u64`. The cast keeps the bits, and is only there for the type.
+///
+pub fn pointer_sized(literal: Expression, var_type: &str) -> Expression {
+    Expression::CastExpression( CastExpressionData {
+        e: Box::new(literal),
+        var_type: var_type.to_string()
+    } )
+}
+
 /// Recurses through an Expression and returns the type it would evaluate to.
 ///
 /// # Arguments:
@@ -245,218 +936,55 @@ pub fn substitute_variable_with_expression (source_expression: &mut Expression,
 /// * A String representation of the type that should return from the top level of the Expression.
 ///
 /// # Remarks:
+/// * The Expression is expected to be type checked already (see `ty_check`); the type is read off
+///   its structure, and no validation is done here.
 ///
 pub fn determine_evaluation_type ( expression: &Expression ) -> String {
-    match ty_check(expression) {
-        Ok(_) => {
-            match *expression {
-                Expression::BinaryExpression(ref b) => {
-                    match b.op {
-                        BinaryOperator::Addition
-                        | BinaryOperator::Subtraction
-                        | BinaryOperator::Multiplication
-                        | BinaryOperator::Division
-                        | BinaryOperator::Modulo => {
-                            let l_type: String = determine_evaluation_type(&*b.left);
-                            let r_type: String = determine_evaluation_type(&*b.right);
-                            // Ensure both operands are numeric types
-                            if (l_type == "bool") || (r_type == "bool") {
-                                rp_error!(
-                                    "Invalid use of binary operator {} on boolean value(s)",
-                                    b.op
-                                );
-                            // Ensure both operand types match
-                            } else if l_type != r_type {
-                                rp_error!(
-                                    "Binary operand types do not match: {} {} {}",
-                                    l_type,
-                                    b.op,
-                                    r_type
-                                );
-                            } else {
-                                l_type
-                            }
-                        },
-                        BinaryOperator::BitwiseLeftShift | BinaryOperator::BitwiseRightShift => {
-                            let l_type: String = determine_evaluation_type(&*b.left);
-                            let r_type: String = determine_evaluation_type(&*b.right);
-
-                            // Ensure both operands are numeric types
-                            if (l_type == "bool") || (r_type == "bool") {
-                                rp_error!(
-                                    "Invalid use of binary operator {} on boolean value(s)",
-                                    b.op
-                                );
-                            //Ensure both operand types are of same signedness
-                            } else if (l_type.starts_with('i') && !r_type.starts_with('i'))
-                                       || (l_type.starts_with('u') && !r_type.starts_with('u')) {
-                                rp_error!(
-                                    "Binary operand types do not match: {} {} {}",
-                                    l_type,
-                                    b.op, r_type
-                                );
-                            } else {
-                                l_type
-                            }
-                        },
-                        BinaryOperator::BitwiseOr
-                        | BinaryOperator::BitwiseAnd
-                        | BinaryOperator::BitwiseXor => {
-                            let l_type: String = determine_evaluation_type(&*b.left);
-                            let r_type: String = determine_evaluation_type(&*b.right);
-                            // Ensure both operand types match
-                            if l_type != r_type {
-                                rp_error!(
-                                    "Binary operand types do not match: {} {} {}",
-                                    l_type,
-                                    b.op,
-                                    r_type
-                                );
-                            } else {
-                                l_type
-                            }
-                        },
-                        BinaryOperator::LessThan
-                        | BinaryOperator::LessThanOrEqual
-                        | BinaryOperator::GreaterThan
-                        | BinaryOperator::GreaterThanOrEqual
-                        | BinaryOperator::SignedMultiplicationDoesNotOverflow
-                        | BinaryOperator::SignedMultiplicationDoesNotUnderflow
-                        | BinaryOperator::UnsignedMultiplicationDoesNotOverflow => {
-                            let l_type: String = determine_evaluation_type(&*b.left);
-                            let r_type: String = determine_evaluation_type(&*b.right);
-                            // Ensure both operands are numeric types
-                            if (l_type == "bool") || (r_type == "bool") {
-                                rp_error!(
-                                    "Invalid use of binary operator {} on boolean value(s)",
-                                    b.op
-                                );
-                            // Ensure both operand types match
-                            } else if l_type != r_type {
-                                rp_error!(
-                                    "Binary operand types do not match: {} {} {}",
-                                    l_type,
-                                    b.op,
-                                    r_type
-                                );
-                            } else {
-                                "bool".to_string()
-                            }
-                        },
-                        BinaryOperator::Equal
-                        | BinaryOperator::NotEqual => {
-                            let l_type: String = determine_evaluation_type(&*b.left);
-                            let r_type: String = determine_evaluation_type(&*b.right);
-                            // Ensure both operand types match
-                            if l_type != r_type {
-                                rp_error!(
-                                    "Binary operand types do not match: {} {} {}",
-                                    l_type,
-                                    b.op,
-                                    r_type
-                                );
-                            } else {
-                                "bool".to_string()
-                            }
-                        },
-                        BinaryOperator::And
-                        | BinaryOperator::Or
-                        | BinaryOperator::Xor
-                        | BinaryOperator::Implication
-                        | BinaryOperator::BiImplication => {
-                            let l_type: String = determine_evaluation_type(&*b.left);
-                            let r_type: String = determine_evaluation_type(&*b.right);
-                            // Ensure both operands are boolean types
-                            if (l_type != "bool") || (r_type != "bool") {
-                                rp_error!(
-                                    "Invalid use of binary operator {} on numeric value(s)",
-                                    b.op
-                                );
-                            // Ensure both operand types match
-                            } else if l_type != r_type {
-                                rp_error!(
-                                    "Binary operand types do not match: {} {} {}",
-                                    l_type,
-                                    b.op,
-                                    r_type
-                                );
-                            } else {
-                                "bool".to_string()
-                            }
-                        }
-                    }
-                },
-                Expression::UnaryExpression(ref u) => {
-                    match u.op {
-                        UnaryOperator::Negation => {
-                            let e_type: String = determine_evaluation_type(&*u.e);
-                            // Ensure operand is a numeric type
-                            if e_type == "bool" {
-                                rp_error!(
-                                    "Invalid use of operator {} on boolean value {}",
-                                    u.op,
-                                    *u.e
-                                );
-                            // Ensure operand is not an unsigned type
-                            } else if is_valid_unsigned(e_type.as_str()) {
-                                rp_error!(
-                                    "Invalid use of operator {} on unsigned value {}",
-                                    u.op,
-                                    *u.e
-                                );
-                            } else {
-                                e_type
-                            }
-                        },
-                        UnaryOperator::BitwiseNot => determine_evaluation_type(&*u.e),
-                        UnaryOperator::Not => {
-                            let e_type: String = determine_evaluation_type(&*u.e);
-                            // Ensure operand is a boolean type
-                            if e_type != "bool" {
-                                rp_error!(
-                                    "Invalid use of operator {} on non-boolean value {}",
-                                    u.op,
-                                    *u.e
-                                );
-                            } else {
-                                e_type
-                            }
-                        }
-                    }
-                },
-                Expression::VariableMapping(ref v) => v.var_type.clone(),
-                Expression::BooleanLiteral(_) => "bool".to_string(),
-                Expression::UnsignedBitVector(ref u) => {
-                    match u.size {
-                        8 => "u8".to_string(),
-                        16 => "u16".to_string(),
-                        32 => "u32".to_string(),
-                        64 => "u64".to_string(),
-                        _ => {
-                            rp_error!(
-                                "Invalid or Unsupported integer type: \"u{}\"",
-                                u.size.to_string()
-                            );
-                        }
-                    }
-                },
-                Expression::SignedBitVector(ref s) => {
-                    match s.size {
-                        8 => "i8".to_string(),
-                        16 => "i16".to_string(),
-                        32 => "i32".to_string(),
-                        64 => "i64".to_string(),
-                        _ => {
-                            rp_error!(
-                                "Invalid or Unsupported integer type: \"i{}\"",
-                                s.size.to_string()
-                            );
-                        }
-                    }
-                }
+    match *expression {
+        Expression::BinaryExpression(ref b) => {
+            match b.op {
+                // The result has the type of the left operand (for shifts, the value shifted)
+                BinaryOperator::Addition
+                | BinaryOperator::Subtraction
+                | BinaryOperator::Multiplication
+                | BinaryOperator::Division
+                | BinaryOperator::Modulo
+                | BinaryOperator::BitwiseLeftShift
+                | BinaryOperator::BitwiseRightShift
+                | BinaryOperator::BitwiseOr
+                | BinaryOperator::BitwiseAnd
+                | BinaryOperator::BitwiseXor => determine_evaluation_type(&*b.left),
+                BinaryOperator::LessThan
+                | BinaryOperator::LessThanOrEqual
+                | BinaryOperator::GreaterThan
+                | BinaryOperator::GreaterThanOrEqual
+                | BinaryOperator::SignedMultiplicationDoesNotOverflow
+                | BinaryOperator::SignedMultiplicationDoesNotUnderflow
+                | BinaryOperator::UnsignedMultiplicationDoesNotOverflow
+                | BinaryOperator::Equal
+                | BinaryOperator::NotEqual
+                | BinaryOperator::And
+                | BinaryOperator::Or
+                | BinaryOperator::Xor
+                | BinaryOperator::Implication
+                | BinaryOperator::BiImplication => "bool".to_string(),
             }
         },
-        Err(e) => rp_error!("{}", e),
+        Expression::UnaryExpression(ref u) => determine_evaluation_type(&*u.e),
+        Expression::VariableMapping(ref v) => v.var_type.clone(),
+        Expression::BooleanLiteral(_) => "bool".to_string(),
+        Expression::UnsignedBitVector(ref u) => format!("u{}", u.size),
+        Expression::SignedBitVector(ref s) => format!("i{}", s.size),
+        Expression::VariantCheck(_) => "bool".to_string(),
+        Expression::QuantifiedExpression(_) => "bool".to_string(),
+        Expression::CastExpression(ref c) => c.var_type.clone(),
+        Expression::ArrayIndex(ref a) => {
+            let array_type = determine_evaluation_type(&a.array);
+            // An index of a non-array value is rejected by `ty_check`
+            element_type(&array_type).unwrap_or(array_type)
+        },
+        Expression::ArrayStore(ref a) => determine_evaluation_type(&a.array),
+        Expression::Old(ref e) => determine_evaluation_type(e),
     }
 }
 
@@ -534,12 +1062,14 @@ pub fn ty_check( expression: &Expression ) -> Result<bool, String> {
                                                 b.op
                                             )
                                         )
-                                    //Ensure both operand types are of same signedness
-                                    } else if (l_type.starts_with('i') && !r_type.starts_with('i'))
-                                            || (l_type.starts_with('u') && !r_type.starts_with('u')) {
+                                    // The shift amount may be of any integer type, as in Rust
+                                    } else if integer_width(&l_type).is_none()
+                                            || integer_width(&r_type).is_none() {
                                         Err(
                                             format!(
-                                                "Binary operand types do not match: {} {} {}",
+                                                "Invalid use of binary operator {} on non-integer \
+                                                value(s): {} {} {}",
+                                                b.op,
                                                 l_type,
                                                 b.op,
                                                 r_type
@@ -746,6 +1276,7 @@ pub fn ty_check( expression: &Expression ) -> Result<bool, String> {
         Expression::VariableMapping(ref v) => {
             if is_valid_unsigned(v.var_type.as_str())
                || is_valid_signed(v.var_type.as_str())
+               || is_valid_array(v.var_type.as_str())
                || v.var_type.as_str() == "bool" {
                 Ok(true)
             } else {
@@ -788,6 +1319,8 @@ pub fn ty_check( expression: &Expression ) -> Result<bool, String> {
                         Err(format!("Out of range value for u64 type: {}", u.value.to_string()))
                     }
                 },
+                // Every u128 value is in range
+                128 => Ok(true),
                 _ => {
                    Err(format!("Invalid or unsupported integer type: \"u{}\"", u.size.to_string()))
                 }
@@ -826,6 +1359,8 @@ pub fn ty_check( expression: &Expression ) -> Result<bool, String> {
                         Err(format!("Out of range value for i64 type: {}", s.value.to_string()))
                     }
                 },
+                // Every i128 value is in range
+                128 => Ok(true),
                 _ => {
                     Err(
                         format!(
@@ -835,8 +1370,270 @@ pub fn ty_check( expression: &Expression ) -> Result<bool, String> {
                     )
                 }
             }
+        },
+        Expression::VariantCheck(ref v) => {
+            // Ensure the value is not of a primitive type
+            if is_valid_unsigned(v.var.var_type.as_str())
+               || is_valid_signed(v.var.var_type.as_str())
+               || v.var.var_type.as_str() == "bool" {
+                Err(format!("Invalid use of \"is\" on non-enum value {}", v.var))
+            } else {
+                Ok(true)
+            }
+        },
+        Expression::QuantifiedExpression(ref q) => {
+            // Ensure the bound variable is an integer
+            if !is_valid_unsigned(q.var.var_type.as_str())
+               && !is_valid_signed(q.var.var_type.as_str()) {
+                return Err(format!("Invalid or unsupported quantified variable type: \"{}\"",
+                                   q.var.var_type));
+            }
+            try!(ty_check(&*q.e));
+            try!(check_bound_variable(&*q.e, &q.var));
+            // Ensure the quantified expression is a boolean
+            let e_type: String = determine_evaluation_type(&*q.e);
+            if e_type != "bool" {
+                Err(format!("Invalid use of quantifier {} on non-boolean value {}",
+                            q.quantifier,
+                            *q.e))
+            } else {
+                Ok(true)
+            }
+        },
+        Expression::CastExpression(ref c) => {
+            try!(ty_check(&*c.e));
+            // Integers and booleans can be cast to any integer type, as in Rust
+            let e_type: String = determine_evaluation_type(&*c.e);
+            if !is_valid_unsigned(c.var_type.as_str()) && !is_valid_signed(c.var_type.as_str()) {
+                Err(format!("Invalid cast to non-integer type {}", c.var_type))
+            } else if !is_valid_unsigned(e_type.as_str())
+                      && !is_valid_signed(e_type.as_str())
+                      && e_type != "bool" {
+                Err(format!("Invalid cast of non-integer value {}", *c.e))
+            } else {
+                Ok(true)
+            }
+        },
+        Expression::ArrayIndex(ref a) => {
+            try!(ty_check(&*a.array));
+            try!(ty_check(&*a.index));
+            // Ensure an array is indexed by a usize
+            let array_type: String = determine_evaluation_type(&*a.array);
+            if element_type(&array_type).is_none() {
+                Err(format!("Invalid index of non-array value {}", *a.array))
+            } else if determine_evaluation_type(&*a.index) != "usize" {
+                Err(format!("Invalid array index {}, which must be a usize", *a.index))
+            } else {
+                Ok(true)
+            }
+        },
+        Expression::ArrayStore(ref a) => {
+            try!(ty_check(&*a.array));
+            try!(ty_check(&*a.index));
+            try!(ty_check(&*a.value));
+            // Ensure the stored value has the type of the elements
+            let array_type: String = determine_evaluation_type(&*a.array);
+            if element_type(&array_type) != Some(determine_evaluation_type(&*a.value)) {
+                Err(format!("Invalid store of {} into {}", *a.value, *a.array))
+            } else {
+                Ok(true)
+            }
+        },
+        Expression::Old(ref e) => ty_check(e)
+    }
+}
+
+/// Returns the type of the elements of an array or slice type (ie: `"u32"` for `"[u32; 4]"`).
+///
+/// # Arguments:
+/// * `var_type` - A string slice
+///
+/// # Return:
+/// * The element type, or `None` if the type is not an array or slice type
+///
+pub fn element_type(var_type: &str) -> Option<String> {
+    if !var_type.starts_with('[') || !var_type.ends_with(']') {
+        return None;
+    }
+    let inner = &var_type[1..var_type.len() - 1];
+    // The length of an array follows the last top-level semicolon
+    let mut depth = 0;
+    let mut separator = None;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '[' | '<' | '(' => depth += 1,
+            ']' | '>' | ')' => depth -= 1,
+            ';' if depth == 0 => separator = Some(i),
+            _ => {},
         }
     }
+    match separator {
+        Some(i) => Some(inner[..i].trim().to_string()),
+        None => Some(inner.trim().to_string()),
+    }
+}
+
+/// Returns the length of an array type (ie: `4` for `"[u32; 4]"`).
+///
+/// # Arguments:
+/// * `var_type` - A string slice
+///
+/// # Return:
+/// * The length, or `None` if the type is a slice type or not an array type
+///
+pub fn array_length(var_type: &str) -> Option<u64> {
+    let element = match element_type(var_type) {
+        Some(element) => element,
+        None => return None,
+    };
+    let rest = var_type[1 + element.len()..var_type.len() - 1].trim();
+    if rest.starts_with(';') {
+        rest[1..].trim().parse::<u64>().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns an Expression for the length of an array or slice.
+///
+/// # Arguments:
+/// * `array` - A variable of array or slice type.
+///
+/// # Return:
+/// * A `usize` literal for an array, or the `usize` field `len` of a slice (ie: `a.len`)
+///
+/// # Remarks:
+/// * Assignments to the elements of a slice replace the slice in the weakest precondition, but
+///   not its `len` field, so the length stays the same.
+///
+pub fn length_of(array: &VariableMappingData) -> Expression {
+    match array_length(&array.var_type) {
+        Some(length) => pointer_sized(Expression::UnsignedBitVector( UnsignedBitVectorData {
+            size: pointer_width(),
+            value: length as u64,
+            high: 0
+        }), "usize"),
+        None => Expression::VariableMapping( VariableMappingData {
+            name: array.name.clone() + ".len",
+            var_type: "usize".to_string()
+        }),
+    }
+}
+
+/// Checks whether an Expression contains an array or slice.
+///
+/// # Arguments:
+/// * `expression` - The Expression to be recursed through.
+///
+pub fn contains_array (expression: &Expression) -> bool {
+    let mut variables = Vec::new();
+    collect_variables(expression, &mut variables);
+    variables.iter().any(|v| element_type(&v.var_type).is_some())
+}
+
+/// Returns the width of an integer type in bits (ie: `32` for `"i32"`, or the pointer width for
+/// `"usize"`).
+///
+/// # Arguments:
+/// * `var_type` - A string slice
+///
+/// # Return:
+/// * The width, or `None` if the type is not a supported integer type
+///
+pub fn integer_width(var_type: &str) -> Option<u8> {
+    if var_type == "isize" || var_type == "usize" {
+        Some(pointer_width())
+    } else if is_valid_unsigned(var_type) || is_valid_signed(var_type) {
+        var_type[1..].parse::<u8>().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns the amount a value is shifted by, as an Expression of the type of the value shifted.
+///
+/// # Arguments:
+/// * `value` - The left operand of a shift.
+/// * `amount` - The right operand of a shift, of an integer type of the same width as `value`.
+///
+/// # Return:
+/// * `amount` cast to the type of `value`, or `amount` itself if the types are the same
+///
+/// # Remarks:
+/// * Shifts with operands of different widths are rewritten by `widen_shift` first, so the cast
+///   only changes the signedness of the amount and keeps its bits.
+///
+pub fn shift_amount(value: &Expression, amount: &Expression) -> Expression {
+    cast_to(amount, &determine_evaluation_type(value))
+}
+
+/// Rewrites a shift whose operands have different widths as a shift at the wider width, since
+/// SMT-LIB shifts need operands of the same width.
+///
+/// # Arguments:
+/// * `shift` - A binary expression.
+///
+/// # Return:
+/// * The shift of the extended operands, truncated to the type of the value shifted, or `None` if
+///   `shift` is not a shift or its operands already have the same width
+///
+/// # Remarks:
+/// * The value is extended according to its signedness, so an arithmetic shift keeps its sign.
+///   The amount is zero-extended, so a negative amount is a large amount as in Rust. Neither is
+///   truncated, so an amount of at least the width of the value shifts every bit out.
+///
+pub fn widen_shift(shift: &BinaryExpressionData) -> Option<Expression> {
+    match shift.op {
+        BinaryOperator::BitwiseLeftShift | BinaryOperator::BitwiseRightShift => {},
+        _ => return None,
+    }
+    let value_type = determine_evaluation_type(&shift.left);
+    let amount_type = determine_evaluation_type(&shift.right);
+    let (value_width, amount_width) = match (integer_width(&value_type),
+                                             integer_width(&amount_type)) {
+        (Some(value_width), Some(amount_width)) if value_width != amount_width => {
+            (value_width, amount_width)
+        },
+        _ => return None,
+    };
+    let wide_type = format!("{}{}", &value_type[..1], cmp::max(value_width, amount_width));
+    let amount = cast_to(&cast_to(&shift.right, &format!("u{}", amount_width)), &wide_type);
+    let wide_shift = Expression::BinaryExpression( BinaryExpressionData {
+        op: shift.op.clone(),
+        left: Box::new(cast_to(&shift.left, &wide_type)),
+        right: Box::new(amount)
+    } );
+    Some(cast_to(&wide_shift, &value_type))
+}
+
+/// Returns an expression cast to a type, or the expression itself if it already has the type.
+pub fn cast_to(e: &Expression, var_type: &str) -> Expression {
+    if determine_evaluation_type(e) == var_type {
+        e.clone()
+    } else {
+        Expression::CastExpression( CastExpressionData {
+            e: Box::new(e.clone()),
+            var_type: var_type.to_string()
+        } )
+    }
+}
+
+/// Checks if argument string is an array or slice of a supported type
+///
+/// # Arguments:
+/// * `var_type` - A string slice
+///
+/// # Return:
+/// * `true` if it is, `false` otherwise
+///
+fn is_valid_array(var_type: &str) -> bool {
+    match element_type(var_type) {
+        Some(ref e_type) => {
+            is_valid_unsigned(e_type) || is_valid_signed(e_type) || e_type == "bool"
+            || is_valid_array(e_type)
+        },
+        None => false,
+    }
 }
 
 /// Checks if argument string matches one of the supported unsigned integer types
@@ -848,13 +1645,15 @@ pub fn ty_check( expression: &Expression ) -> Result<bool, String> {
 /// * `true` if it matches, `false` otherwise
 ///
 /// # Remarks:
-/// * Current supported types: u8, u16, u32, u64
+/// * Current supported types: u8, u16, u32, u64, u128, usize
 ///
 fn is_valid_unsigned(var_type: &str) -> bool {
     (var_type == "u8")
     || (var_type == "u16")
     || (var_type == "u32")
     || (var_type == "u64")
+    || (var_type == "u128")
+    || (var_type == "usize")
 }
 
 /// Checks if argument string matches one of the supported signed integer types
@@ -866,11 +1665,13 @@ fn is_valid_unsigned(var_type: &str) -> bool {
 /// * `true` if it matches, `false` otherwise
 ///
 /// # Remarks:
-/// * Current supported types: i8, i16, i32, i64
+/// * Current supported types: i8, i16, i32, i64, i128, isize
 ///
 fn is_valid_signed(var_type: &str) -> bool {
     (var_type == "i8")
     || (var_type == "i16")
     || (var_type == "i32")
     || (var_type == "i64")
+    || (var_type == "i128")
+    || (var_type == "isize")
 }
@@ -44,21 +44,25 @@ extern crate rustc_errors as errors;
 use rustc_data_structures::indexed_vec::Idx;
 use rustc_plugin::Registry;
 use rustc::mir::repr::{Mir, BasicBlock, BasicBlockData, Arg, Temp, Var, ArgDecl, TempDecl, VarDecl};
+use rustc::mir::repr::{TerminatorKind, Operand, Constant, Literal, Lvalue};
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::hir::map::Node;
 use rustc::mir::transform::{Pass, MirPass, MirSource};
 use rustc::ty::{TyCtxt, FnOutput};
 use syntax::feature_gate::AttributeType;
 use syntax::parse::token::InternedString;
-use syntax::ast::MetaItemKind;
-use errors::{ColorConfig, Handler};
-use syntax::codemap::CodeMap;
-use std::rc::Rc;
-use std::process;
+use syntax::ast::{LitKind, MetaItemKind, NodeId};
+use std::collections::HashMap;
+use std::env;
 
 // Local imports
-use expression::{Expression, BinaryOperator, BinaryExpressionData};
+use expression::{Expression, BinaryOperator, BinaryExpressionData, restore_old_values};
+use expression::resolve_variant_checks;
 use parser::*;
 use smt_output::*;
 use weakest_precondition::*;
+use reporting::{Error, emit};
 
 // rustproof modules
 mod expression;
@@ -73,17 +77,65 @@ mod tests;
 #[plugin_registrar]
 pub fn registrar(reg: &mut Registry) {
     // If debug is an argument, set the debug flag to true
+    // If deny is an argument, verification conditions that are not valid become compile errors
+    // pointer_width = "32" or "64" sets the width of isize and usize, which is otherwise the
+    // pointer width of the target
+    // solver = "<name>" sets the SMT solver, which is otherwise read from the RUSTPROOF_SOLVER
+    // environment variable, or else Z3
     let mut debug = false;
+    let mut deny = false;
+    let target_width = reg.sess.target.target.target_pointer_width.parse::<u8>();
+    let mut pointer_width = target_width.unwrap_or(64);
+    let mut solver = Solver::default();
+    if let Ok(name) = env::var(SOLVER_VARIABLE) {
+        match Solver::from_name(&name) {
+            Some(named) => solver = named,
+            None => reg.sess.err(&format!("{} must be one of {}, not \"{}\"",
+                                          SOLVER_VARIABLE, SOLVER_NAMES.join(", "), name)),
+        }
+    }
     for arg in reg.args() {
         if arg.clone().unwrap().node == MetaItemKind::Word(InternedString::new("debug")) {
             debug = true;
         }
+        else if arg.clone().unwrap().node == MetaItemKind::Word(InternedString::new("deny")) {
+            deny = true;
+        }
+        else if let MetaItemKind::NameValue(ref name, ref value) = arg.node {
+            let value = match value.node {
+                LitKind::Str(ref value, _) => value.to_string(),
+                _ => String::new(),
+            };
+            if *name == "pointer_width" {
+                match value.parse::<u8>() {
+                    Ok(width) if width == 32 || width == 64 => pointer_width = width,
+                    _ => reg.sess.span_err(arg.span, "pointer_width must be \"32\" or \"64\""),
+                }
+            }
+            else if *name == "solver" {
+                match Solver::from_name(&value) {
+                    Some(named) => solver = named,
+                    None => reg.sess.span_err(arg.span,
+                                              &format!("solver must be one of {}",
+                                                       SOLVER_NAMES.join(", "))),
+                }
+            }
+            else {
+                reg.sess.span_err(arg.span, "unrecognized plugin argument");
+            }
+        }
         else {
-            rp_error!("unrecognized plugin argument");
+            reg.sess.span_err(arg.span, "unrecognized plugin argument");
         }
     }
 
-    let visitor = MirVisitor { debug: debug };
+    let visitor = MirVisitor {
+        debug: debug,
+        deny: deny,
+        pointer_width: pointer_width,
+        solver: solver,
+        results: Vec::new()
+    };
 
     reg.register_attribute("condition".to_string(), AttributeType::Whitelisted);
     reg.register_mir_pass(Box::new(visitor));
@@ -102,26 +154,65 @@ pub struct MirData<'tcx> {
     var_data: Vec<&'tcx VarDecl<'tcx>>,
     temp_data: Vec<&'tcx TempDecl<'tcx>>,
     func_return_type: String,
+    loop_invariants: HashMap<usize, Expression>,
+    loop_check: LoopCheck,
+    enum_variants: HashMap<String, Vec<String>>,
+    struct_fields: HashMap<String, Vec<(String, String)>>,
+    references: HashMap<String, Lvalue<'tcx>>,
+    obligations: Vec<Obligation>,
+    call_contracts: HashMap<usize, CallContract>,
 }
 
 // required struct for Pass impl
-struct MirVisitor { debug: bool }
+struct MirVisitor {
+    debug: bool,
+    deny: bool,
+    // The width of isize and usize in bits
+    pointer_width: u8,
+    // The SMT solver verification conditions are checked with
+    solver: Solver,
+    // The result of every verification condition checked so far, with the name it was printed
+    // under, summarized when the pass is dropped
+    results: Vec<(String, VerificationResult)>,
+}
 
 /// This must exist and must be blank
 impl <'tcx> Pass for MirVisitor {}
 
+// The pass is dropped once every function in the crate has been visited
+impl Drop for MirVisitor {
+    fn drop(&mut self) {
+        print_summary(&self.results);
+    }
+}
+
 /// Sets up the compiler to go through MIR code.
 ///
 /// # Remarks:
 impl <'tcx> MirPass<'tcx> for MirVisitor {
     // Visit the MIR of the entire program
     fn run_pass<'a>(&mut self, tcx: TyCtxt<'a, 'tcx, 'tcx>, src: MirSource, mir: &mut Mir<'tcx>) {
+        let fn_span = tcx.map.span(src.item_id());
+
+        // An error abandons this function only. It is reported at the function, unless it has a
+        // more precise span of its own
+        if let Err(e) = self.check_fn(tcx, src, mir) {
+            emit(tcx.sess, fn_span, e);
+        }
+    }
+}
+
+impl MirVisitor {
+    // Verifies a function that has a condition attribute, see `run_pass`
+    fn check_fn<'a, 'tcx>(&mut self, tcx: TyCtxt<'a, 'tcx, 'tcx>, src: MirSource, mir: &Mir<'tcx>)
+                          -> Result<(), Error> {
         let debug = self.debug;
+        expression::set_pointer_width(self.pointer_width);
+        let solver = self.solver;
         // Clear the stored attributes in the builder
         let mut pre_string = "".to_string();
         let mut post_string = "".to_string();
-        let pre_expr;
-        let post_expr;
+        let mut inv_strings = Vec::new();
 
         // Store relevant data
         let item_id = src.item_id();
@@ -130,86 +221,503 @@ impl <'tcx> MirPass<'tcx> for MirVisitor {
         let attrs = tcx.map.attrs(item_id);
 
         // TODO: Find a better way to do this
+        let mut condition_spans = Vec::new();
         for attr in attrs {
-            parse_attribute(&mut pre_string, &mut post_string, attr);
+            try!(parse_attribute(&mut pre_string, &mut post_string, &mut inv_strings, attr));
+            let spans = parser::condition_spans(attr);
+            if !spans.is_empty() {
+                condition_spans = spans;
+            }
         }
 
         // TODO: Find a better condition check
-        if pre_string != "" {
-            // Parse the pre- and postcondition arguments
-            pre_expr = Some(parser::parse_condition(pre_string.as_str()));
-            post_expr = Some(parser::parse_condition(post_string.as_str()));
-
-            // Struct to carry MIR data to later stages
-            let mut data = MirData {
-                block_data: Vec::new(),
-                arg_data: Vec::new(),
-                var_data: Vec::new(),
-                temp_data: Vec::new(),
-                func_return_type: "".to_string(),
-            };
+        if pre_string == "" {
+            return Ok(());
+        }
 
-            // Get the basic block data
-            for index in 0..mir.basic_blocks().len() {
-                let block = BasicBlock::new(index);
-                data.block_data.push(&mir[block]);
-            }
+        // Parse the pre- and postcondition arguments
+        let mut pre_expr = try!(parser::parse_condition(pre_string.as_str(), condition_spans[0]));
+        let mut post_expr = try!(parser::parse_condition(post_string.as_str(), condition_spans[1]));
+
+        // Struct to carry MIR data to later stages
+        let mut data = MirData {
+            block_data: Vec::new(),
+            arg_data: Vec::new(),
+            var_data: Vec::new(),
+            temp_data: Vec::new(),
+            func_return_type: "".to_string(),
+            loop_invariants: HashMap::new(),
+            loop_check: LoopCheck::Entry,
+            enum_variants: HashMap::new(),
+            struct_fields: HashMap::new(),
+            references: HashMap::new(),
+            obligations: Vec::new(),
+            call_contracts: HashMap::new(),
+        };
+
+        // Get the basic block data
+        for index in 0..mir.basic_blocks().len() {
+            let block = BasicBlock::new(index);
+            data.block_data.push(&mir[block]);
+        }
 
-            // Get the function argument declarations
-            for index in 0..mir.arg_decls.len() {
-                let arg = Arg::new(index);
-                data.arg_data.push(&mir.arg_decls[arg]);
+        // Get the function argument declarations
+        for index in 0..mir.arg_decls.len() {
+            let arg = Arg::new(index);
+            data.arg_data.push(&mir.arg_decls[arg]);
+            record_enum_variants(mir.arg_decls[arg].ty, &mut data);
+            record_struct_fields(mir.arg_decls[arg].ty, &mut data);
+        }
+
+        // Get the temp declarations
+        for index in 0..mir.temp_decls.len() {
+            let temp = Temp::new(index);
+            data.temp_data.push(&mir.temp_decls[temp]);
+            record_enum_variants(mir.temp_decls[temp].ty, &mut data);
+            record_struct_fields(mir.temp_decls[temp].ty, &mut data);
+        }
+
+        // Get the variable declarations
+        for index in 0..mir.var_decls.len() {
+            let var = Var::new(index);
+            data.var_data.push(&mir.var_decls[var]);
+            record_enum_variants(mir.var_decls[var].ty, &mut data);
+            record_struct_fields(mir.var_decls[var].ty, &mut data);
+        }
+
+        // Get the return type
+        let return_ty = match mir.return_ty {
+            FnOutput::FnConverging(t) => t,
+            FnOutput::FnDiverging => {
+                rp_bail!("Unsupported fn {}(..), which never returns", name);
+            }
+        };
+        record_enum_variants(return_ty, &mut data);
+        record_struct_fields(return_ty, &mut data);
+        data.func_return_type = type_name(return_ty);
+
+        // Find the place each reference made in the function borrows
+        try!(record_references(&mut data));
+
+        if debug {
+            println!("Printing basic blocks...");
+            for index in 0..data.block_data.len() {
+                println!("bb{:?}\n{:#?}\n", index, data.block_data[index]);
             }
+        }
 
-            // Get the temp declarations
-            for index in 0..mir.temp_decls.len() {
-                let temp = Temp::new(index);
-                data.temp_data.push(&mir.temp_decls[temp]);
+        // Errors in the pre- and postcondition are reported at the conditions
+        let (pre_span, post_span) = (condition_spans[0], condition_spans[1]);
+
+        // Replace checks of enum variants with checks of their discriminants
+        try!(resolve_variants(&mut pre_expr, &data).map_err(|e| e.or_span(pre_span)));
+        try!(resolve_variants(&mut post_expr, &data).map_err(|e| e.or_span(post_span)));
+
+        // Replace the names of struct fields with their positions
+        try!(resolve_fields(&mut pre_expr, &data).map_err(|e| e.or_span(pre_span)));
+        try!(resolve_fields(&mut post_expr, &data).map_err(|e| e.or_span(post_span)));
+
+        // The precondition is evaluated on entry, where old(..) values are current. The
+        // postcondition refers to them through snapshots
+        try!(resolve_old(&mut pre_expr, "").map_err(|e| e.or_span(pre_span)));
+        try!(resolve_old(&mut post_expr, expression::OLD_SUFFIX).map_err(|e| e.or_span(post_span)));
+
+        // Find the contract of each function called, keyed by the block whose terminator calls it
+        for index in 0..data.block_data.len() {
+            let block = data.block_data[index];
+            let terminator = block.terminator();
+            if let TerminatorKind::Call{ref func, ..} = terminator.kind {
+                let span = terminator.source_info.span;
+                // A recursive call could only be checked against the function's own
+                // contract, which would not show that the recursion ends
+                if let Operand::Constant(Constant{ literal: Literal::Item{def_id: callee, ..}, .. }) = *func {
+                    if callee == def_id {
+                        rp_span_bail!(span, "Unsupported recursive call in fn {}(..)", name);
+                    }
+                }
+                let contract = try!(gen_call_contract(tcx, func, &mut data)
+                                        .map_err(|e| e.or_span(span)));
+                match contract {
+                    Some(mut contract) => {
+                        // The callee's entry values are the arguments of the call. Only
+                        // values written through `&mut` arguments differ on return
+                        try!(resolve_old(&mut contract.pre, "").map_err(|e| e.or_span(span)));
+                        try!(resolve_old(&mut contract.post, expression::OLD_SUFFIX)
+                                 .map_err(|e| e.or_span(span)));
+                        data.call_contracts.insert(index, contract);
+                    },
+                    None => {
+                        let callee = match *func {
+                            Operand::Constant(Constant{ literal: Literal::Item{def_id, ..}, .. }) => {
+                                tcx.item_path_str(def_id)
+                            },
+                            _ => format!("{:?}", func),
+                        };
+                        // Panics are handled by gen, everything else is opaque
+                        if !callee.contains("begin_panic") {
+                            rp_warn!(tcx.sess, span,
+                                     "fn {}(..) calls {}, which has no condition attribute. \
+                                     The call is treated as opaque: nothing is assumed about \
+                                     its result.", name, callee);
+                        }
+                    }
+                }
             }
+        }
+
+        // Pair each loop with its invariant, in the order the loops appear in the source
+        let loop_headers = find_loop_headers(&data);
+        if loop_headers.len() > inv_strings.len() {
+            // The first loop without an invariant is reported at its header
+            let header = loop_headers[inv_strings.len()];
+            let span = data.block_data[header].terminator().source_info.span;
+            rp_span_bail!(span,
+                          "fn {}(..) contains {} loop(s) but {} invariant(s) were provided. \
+                          The loop with its header at bb{} has no invariant.",
+                          name, loop_headers.len(), inv_strings.len(), header);
+        }
+        if loop_headers.len() < inv_strings.len() {
+            // The first invariant without a loop is reported at the invariant
+            rp_span_bail!(condition_spans[2 + loop_headers.len()],
+                          "fn {}(..) contains {} loop(s) but {} invariant(s) were provided.",
+                          name, loop_headers.len(), inv_strings.len());
+        }
+        for ((header, inv_string), span) in loop_headers.iter()
+                                                .zip(inv_strings.iter())
+                                                .zip(condition_spans[2..].iter()) {
+            let mut invariant = try!(parser::parse_condition(inv_string.as_str(), *span));
+            try!(resolve_variants(&mut invariant, &data).map_err(|e| e.or_span(*span)));
+            try!(resolve_fields(&mut invariant, &data).map_err(|e| e.or_span(*span)));
+            resolve_local_names(&mut invariant, &data);
+            try!(resolve_old(&mut invariant, expression::OLD_SUFFIX).map_err(|e| e.or_span(*span)));
+            data.loop_invariants.insert(*header, invariant);
+        }
 
-            // Get the variable declarations
-            for index in 0..mir.var_decls.len() {
-                let var = Var::new(index);
-                data.var_data.push(&mir.var_decls[var]);
+        // Counterexamples refer to variables by their names in the source
+        let names = gen_source_names(&data);
+
+        // The postcondition is checked wherever the function returns
+        let post = add_obligation(post_expr, "postcondition", post_span, &mut data);
+
+        // Generate the weakest precondition
+        data.loop_check = LoopCheck::Entry;
+        let mut weakest_precondition = try!(gen_target(0, &mut data, &post, debug));
+        // Snapshots of the entry values are the values on entry
+        restore_old_values(&mut weakest_precondition);
+
+        // Create the verification condition, P -> WP
+        let verification_condition: Expression = Expression::BinaryExpression( BinaryExpressionData{
+            op: BinaryOperator::Implication,
+            left: Box::new(pre_expr),
+            right: Box::new(weakest_precondition)
+        } );
+        let vc_name = format!("{}(..)", name);
+        let (result, failed) = try!(check_obligations(tcx, &verification_condition, &vc_name,
+                                                      &names, &data.obligations, solver, debug));
+        if self.deny {
+            deny_result(tcx, item_id, &vc_name, &result, &failed);
+        }
+        self.results.push((vc_name, result));
+
+        // Each loop must preserve its invariant, and the invariant must be enough to establish
+        // whatever follows the loop: I -> WP
+        for header in loop_headers {
+            let invariant = data.loop_invariants[&header].clone();
+            for check in vec![LoopCheck::Preservation(header), LoopCheck::Exit(header)] {
+                data.loop_check = check;
+                let weakest_precondition = try!(gen(header, &mut data, &post, debug));
+                let verification_condition = Expression::BinaryExpression( BinaryExpressionData{
+                    op: BinaryOperator::Implication,
+                    left: Box::new(invariant.clone()),
+                    right: Box::new(weakest_precondition)
+                } );
+                let label = match check {
+                    LoopCheck::Preservation(_) => "invariant preservation",
+                    _ => "invariant exit",
+                };
+                let vc_name = format!("{}(..) loop at bb{} {}", name, header, label);
+                let (result, failed) = try!(check_obligations(tcx, &verification_condition,
+                                                              &vc_name, &names,
+                                                              &data.obligations, solver, debug));
+                if self.deny {
+                    deny_result(tcx, item_id, &vc_name, &result, &failed);
+                }
+                self.results.push((vc_name, result));
             }
+        }
+        Ok(())
+    }
+}
 
-            // Get the return type
-            data.func_return_type = match mir.return_ty {
-                FnOutput::FnConverging(t) => {
-                    t.to_string()
-                },
-                _ => { unimplemented!(); }
-            };
+/// Reports a verification condition that is not valid as a compile error.
+///
+/// # Arguments:
+/// * `tcx` - The type context of the crate being compiled.
+/// * `item_id` - The function the verification condition belongs to.
+/// * `name` - Identifies the function (and loop, if any) the verification condition belongs to.
+/// * `result` - The result of checking the verification condition.
+/// * `failed` - The obligations of the verification condition that were not shown to hold.
+///
+/// # Remarks:
+/// * The error points at the function, with a note pointing at its condition attribute, and a
+///   note pointing at each obligation that was not shown to hold.
+///
+fn deny_result<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                         item_id: NodeId,
+                         name: &str,
+                         result: &VerificationResult,
+                         failed: &Vec<Obligation>) {
+    let message = match *result {
+        VerificationResult::Valid => return,
+        VerificationResult::Invalid{..} => "verification condition is not valid",
+        VerificationResult::Unknown => "verification condition could not be decided",
+        VerificationResult::Error(..) => "verification condition could not be checked",
+    };
+
+    let mut err = tcx.sess.struct_span_err(tcx.map.span(item_id),
+                                           &format!("fn {}: {}", name, message));
+    for attr in tcx.map.attrs(item_id) {
+        if let MetaItemKind::List(ref attribute_name, _) = attr.node.value.node {
+            if attribute_name == "condition" {
+                err.span_note(attr.span, "the condition is given here");
+            }
+        }
+    }
+    for obligation in failed {
+        err.span_note(obligation.span, &format!("{} is not shown to hold here", obligation.kind));
+    }
+    err.emit();
+}
 
-            if debug {
-                println!("Printing basic blocks...");
-                for index in 0..data.block_data.len() {
-                    println!("bb{:?}\n{:#?}\n", index, data.block_data[index]);
+/// Looks up the contract of a called function.
+///
+/// # Arguments:
+/// * `tcx` - The type context of the crate being compiled.
+/// * `func` - The function operand of a `Call` terminator.
+/// * `data` - Receives the fields of the struct types in the called function's signature.
+///
+/// # Return Value:
+/// * Returns the pre- and postcondition of the called function, or `None` if it has no condition
+///   attribute or is not called directly, or the error found in its conditions.
+///
+/// # Remarks:
+/// * The names of enum variants and struct fields in the contract are resolved against the called
+///   function's argument and return types, which the calling function need not use itself.
+///
+fn gen_call_contract<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                               func: &Operand<'tcx>,
+                               data: &mut MirData<'tcx>)
+                               -> Result<Option<CallContract>, Error> {
+    if let Operand::Constant(ref constant) = *func {
+        if let Literal::Item{def_id, ..} = constant.literal {
+            let mut pre_string = "".to_string();
+            let mut post_string = "".to_string();
+            let mut inv_strings = Vec::new();
+            let mut condition_spans = Vec::new();
+            for attr in tcx.get_attrs(def_id).iter() {
+                try!(parse_attribute(&mut pre_string, &mut post_string, &mut inv_strings, attr));
+                let spans = parser::condition_spans(attr);
+                if !spans.is_empty() {
+                    condition_spans = spans;
                 }
             }
 
-            // Generate the weakest precondition
-            let weakest_precondition = gen(0, &mut data, &post_expr, debug);
+            if pre_string != "" {
+                let arg_names = try!(gen_arg_names(tcx, def_id));
+                // Errors in the callee's conditions are reported at the conditions
+                let (pre_span, post_span) = (condition_spans[0], condition_spans[1]);
+                let mut pre = try!(parser::parse_condition(pre_string.as_str(), pre_span));
+                let mut post = try!(parser::parse_condition(post_string.as_str(), post_span));
+
+                // The types of the callee's arguments and return value, by name
+                let fn_ty = tcx.lookup_item_type(def_id).ty;
+                let sig = fn_ty.fn_sig().skip_binder();
+                let mut var_types = HashMap::new();
+                let mut types = Vec::new();
+                for (arg_name, &arg_ty) in arg_names.iter().zip(sig.inputs.iter()) {
+                    record_struct_fields(arg_ty, data);
+                    var_types.insert(arg_name.clone(), referent_type(&type_name(arg_ty)));
+                    types.push(arg_ty);
+                }
+                if let FnOutput::FnConverging(return_ty) = sig.output {
+                    record_struct_fields(return_ty, data);
+                    var_types.insert("return".to_string(), referent_type(&type_name(return_ty)));
+                    types.push(return_ty);
+                }
 
-            // Create the verification condition, P -> WP
-            let verification_condition: Expression = Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::Implication,
-                left: Box::new(pre_expr.as_ref().unwrap().clone()),
-                right: Box::new(weakest_precondition.as_ref().unwrap().clone())
-            } );
+                // Variant checks are resolved with the callee's own enums
+                let enum_variants = signature_enum_variants(&types);
+                for (condition, span) in vec![(&mut pre, pre_span), (&mut post, post_span)] {
+                    try!(resolve_variant_checks(condition, &enum_variants)
+                             .map_err(|e| Error::at(span, e)));
+                    try!(resolve_field_names(condition, &var_types, &data.struct_fields)
+                             .map_err(|e| e.or_span(span)));
+                }
 
-            // FIXME: Debug should not be a const; it must be user-facing
-            if debug {
-                println!("vc: {}\n", verification_condition);
-            }
-            // Check that the verification condition is correctly typed
-            match expression::ty_check(&verification_condition) {
-                Ok(_) => {},
-                Err(e) => rp_error!("{}", e),
+                return Ok(Some(CallContract {
+                    name: tcx.item_path_str(def_id),
+                    arg_names: arg_names,
+                    pre: pre,
+                    post: post,
+                }));
             }
+        }
+    }
+    Ok(None)
+}
 
-            // Output to SMT-LIB format
-            gen_smtlib(&verification_condition, name, debug);
+/// Returns the names of a function's formal arguments, in order.
+///
+/// # Arguments:
+/// * `tcx` - The type context of the crate being compiled.
+/// * `def_id` - The function whose arguments are named.
+///
+/// # Remarks:
+/// * Functions from other crates are named using their crate metadata.
+///
+fn gen_arg_names<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId)
+                           -> Result<Vec<String>, Error> {
+    let node_id = match tcx.map.as_local_node_id(def_id) {
+        Some(node_id) => node_id,
+        None => return Ok(tcx.sess.cstore.method_arg_names(def_id)),
+    };
+
+    let decl = match tcx.map.get(node_id) {
+        Node::NodeItem(&hir::Item{ node: hir::ItemFn(ref decl, ..), .. }) => decl,
+        Node::NodeImplItem(&hir::ImplItem{ node: hir::ImplItemKind::Method(ref sig, _), .. }) => {
+            &sig.decl
+        },
+        _ => rp_bail!("Unsupported call to {}", tcx.item_path_str(def_id)),
+    };
+
+    decl.inputs.iter().map(|arg| {
+        match arg.pat.node {
+            hir::PatKind::Binding(_, ref name, _) => Ok(name.node.as_str().to_string()),
+            _ => rp_bail!("Unsupported argument pattern in {}", tcx.item_path_str(def_id)),
         }
+    }).collect()
+}
+
+/// Maps the names of a function's arguments and variables in verification conditions to their
+/// names in the source.
+///
+/// # Arguments:
+/// * `data` - Contains the argument and variable declarations from the MIR pass.
+///
+/// # Remarks:
+/// * Temps have no name in the source, and are left out.
+///
+fn gen_source_names(data: &MirData) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for arg in data.arg_data.iter() {
+        let name = arg.debug_name.as_str().to_string();
+        names.insert(name.clone(), name);
+    }
+    for (index, var) in data.var_data.iter().enumerate() {
+        names.insert(format!("var{}", index), var.name.as_str().to_string());
+    }
+    names
+}
+
+/// Type checks a verification condition, hands it to the SMT solver, and prints the result.
+///
+/// # Arguments:
+/// * `vc` - The verification condition as an Expression.
+/// * `names` - The source name of each variable in the verification condition that has one.
+/// * `solver` - The SMT solver to check the verification condition with.
+/// * `debug` - A flag to enable/disable debug printing.
+///
+/// # Return Value:
+/// * Returns the result of checking the verification condition, or the error that kept it from
+///   being checked.
+///
+fn check_vc(vc: &Expression, names: &HashMap<String, String>, solver: Solver, debug: bool)
+            -> Result<VerificationResult, Error> {
+    // FIXME: Debug should not be a const; it must be user-facing
+    if debug {
+        println!("vc: {}\n", vc);
+    }
+    // Check that the verification condition is correctly typed, and that the solver can check
+    // the type of each variable in it
+    try!(expression::ty_check(vc));
+    try!(check_sorts(vc));
+    // Quantifiers need a quantified logic, which not every solver supports
+    if expression::contains_quantifier(vc) && !solver.supports_quantifiers() {
+        rp_bail!("The conditions use quantifiers, which {} does not support; choose a solver that \
+                  does, such as z3", solver.name());
+    }
+
+    // Output to SMT-LIB format
+    Ok(gen_smtlib(vc, names, solver, debug))
+}
+
+/// Checks each obligation of a verification condition on its own, and prints the results.
+///
+/// # Arguments:
+/// * `tcx` - The type context of the crate being compiled.
+/// * `vc` - The verification condition as an Expression, with obligations made by
+///          `add_obligation`.
+/// * `name` - Identifies the function (and loop, if any) the verification condition belongs to.
+/// * `names` - The source name of each variable in the verification condition that has one.
+/// * `obligations` - Every obligation made for the function.
+/// * `solver` - The SMT solver to check the obligations with.
+/// * `debug` - A flag to enable/disable debug printing.
+///
+/// # Return Value:
+/// * Returns the result of the whole verification condition, and the obligations that were not
+///   shown to hold.
+///
+/// # Remarks:
+/// * The verification condition is valid when every obligation holds. Otherwise its result is
+///   that of the first obligation that can fail, or else the first one that could not be
+///   decided or checked.
+///
+fn check_obligations<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                               vc: &Expression,
+                               name: &str,
+                               names: &HashMap<String, String>,
+                               obligations: &Vec<Obligation>,
+                               solver: Solver,
+                               debug: bool)
+                               -> Result<(VerificationResult, Vec<Obligation>), Error> {
+    let indices = find_obligations(vc);
+    if indices.is_empty() {
+        let result = try!(check_vc(vc, names, solver, debug));
+        print_result(name, &result);
+        return Ok((result, Vec::new()));
+    }
+
+    let mut results = Vec::new();
+    for index in indices {
+        let selected = select_obligation(vc, index, obligations.len());
+        let result = try!(check_vc(&selected, names, solver, debug));
+        results.push((obligations[index].clone(), result));
     }
+
+    // The first obligation that can fail, or else the first that could not be decided or checked
+    let result = results.iter()
+                        .map(|&(_, ref result)| result.clone())
+                        .find(|result| match *result {
+                            VerificationResult::Invalid{..} => true,
+                            _ => false,
+                        })
+                        .or_else(|| {
+                            results.iter()
+                                   .map(|&(_, ref result)| result.clone())
+                                   .find(|result| *result != VerificationResult::Valid)
+                        })
+                        .unwrap_or(VerificationResult::Valid);
+
+    print_result(name, &result);
+    let codemap = tcx.sess.codemap();
+    for &(ref obligation, ref result) in results.iter() {
+        let description = format!("{} at {}", obligation.kind, codemap.span_to_string(obligation.span));
+        print_obligation_result(&description, result);
+    }
+
+    let failed = results.into_iter()
+                        .filter(|&(_, ref result)| *result != VerificationResult::Valid)
+                        .map(|(obligation, _)| obligation)
+                        .collect();
+    Ok((result, failed))
 }
@@ -11,15 +11,16 @@
 //! Parses rustproof attribute definitions.
 
 extern crate syntax;
+extern crate lalrpop_util;
 
 mod expression_parser;
 
 use syntax::ast::{MetaItemKind, Attribute_};
-use syntax::codemap::{Spanned, CodeMap};
+use syntax::codemap::{Spanned, Span, BytePos};
 use expression::{Expression, ty_check};
-use std::process;
-use std::rc::Rc;
-use errors::{ColorConfig, Handler};
+use reporting::Error;
+use self::lalrpop_util::ParseError;
+use std::cmp;
 
 /// Analyzes an attribute on a function in the compiled code, and if the attribute is "condition",
 /// ensures correct usage. If usage is correct, it stores the argument strings.
@@ -27,75 +28,172 @@ use errors::{ColorConfig, Handler};
 /// # Arguments:
 /// * `pre_string` - Empty string. Will contain a user-submitted precondition if found.
 /// * `post_string` - Empty string. Will contain a user-submitted postcondition if found.
+/// * `inv_strings` - Empty vector. Will contain any user-submitted loop invariants, in order.
 /// * `attr` - The attribute being analyzed.
 ///
+/// # Return Value:
+/// * Returns an error at the part of the attribute that is used incorrectly.
+///
 /// # Remarks:
 /// * Currently supported `ConstInt`: `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`, `bool`
+/// * Any number of `inv` arguments may follow `pre` and `post`, one for each loop in the function.
 ///
 pub fn parse_attribute(pre_string: &mut String,
                        post_string: &mut String,
-                       attr: &Spanned<Attribute_>) {
+                       inv_strings: &mut Vec<String>,
+                       attr: &Spanned<Attribute_>)
+                       -> Result<(), Error> {
     if let MetaItemKind::List(ref attribute_name, ref args) = attr.node.value.node {
         // Ignore if not a condition attribute
         if attribute_name == "condition" {
-            // Only accept if exactly 2 arguments
-            if args.len() != 2 {
-                rp_error!("Condition attribute must have exactly 2 arguments.");
+            // Only accept if at least 2 arguments
+            if args.len() < 2 {
+                rp_span_bail!(attr.span, "Condition attribute must have at least 2 arguments.");
             }
             // Parse the first argument
             if let MetaItemKind::NameValue(ref i_string, ref literal) = args[0].node {
                 if i_string != "pre" {
-                    rp_error!( "The first argument must be named \"pre\". {} was provided.",
-                               i_string);
+                    rp_span_bail!(args[0].span,
+                                  "The first argument must be named \"pre\". {} was provided.",
+                                  i_string);
                 }
                 // Get the argument
                 if let syntax::ast::LitKind::Str(ref i_string, _) = literal.node {
                     *pre_string = i_string.to_string();
                 } else {
-                    rp_error!("Conditions must be strings. \
-                              Try wrapping conditions in quotation marks.");
+                    rp_span_bail!(literal.span,
+                                  "Conditions must be strings. \
+                                  Try wrapping conditions in quotation marks.");
                 }
             } else {
-                        rp_error!("The first argument must be named \"pre\".");
+                rp_span_bail!(args[0].span, "The first argument must be named \"pre\".");
             }
             // Parse the second argument
             if let MetaItemKind::NameValue(ref i_string, ref literal) = args[1].node {
                 if i_string != "post" {
-                    rp_error!( "The second argument must be named \"post\". {} was provided.",
-                               i_string);
+                    rp_span_bail!(args[1].span,
+                                  "The second argument must be named \"post\". {} was provided.",
+                                  i_string);
                 }
                 // Get the argument
                 if let syntax::ast::LitKind::Str(ref i_string, _) = literal.node {
                     *post_string = i_string.to_string();
                 } else {
-                    rp_error!("Conditions must be strings. \
-                              Try wrapping conditions in quotation marks.");
+                    rp_span_bail!(literal.span,
+                                  "Conditions must be strings. \
+                                  Try wrapping conditions in quotation marks.");
                 }
             } else {
-                rp_error!("The second argument must be named \"post\".");
+                rp_span_bail!(args[1].span, "The second argument must be named \"post\".");
+            }
+            // Parse any loop invariant arguments
+            for arg in &args[2..] {
+                if let MetaItemKind::NameValue(ref i_string, ref literal) = arg.node {
+                    if i_string != "inv" {
+                        rp_span_bail!(arg.span,
+                                      "Arguments after \"post\" must be named \"inv\". \
+                                      {} was provided.",
+                                      i_string);
+                    }
+                    // Get the argument
+                    if let syntax::ast::LitKind::Str(ref i_string, _) = literal.node {
+                        inv_strings.push(i_string.to_string());
+                    } else {
+                        rp_span_bail!(literal.span,
+                                      "Conditions must be strings. \
+                                      Try wrapping conditions in quotation marks.");
+                    }
+                } else {
+                    rp_span_bail!(arg.span, "Arguments after \"post\" must be named \"inv\".");
+                }
             }
         } // Ignore if not a condition attribute
     }
+    Ok(())
 }
 
-/// Calls the expression parser on a given precondition or postcondition.
+/// Finds the spans of the condition strings in an attribute.
+///
+/// # Arguments:
+/// * `attr` - The attribute being analyzed.
+///
+/// # Return Value:
+/// * Returns the spans of the `pre`, `post`, and `inv` strings, in the order they are given, or an
+///   empty vector if the attribute is not a condition attribute.
+///
+pub fn condition_spans(attr: &Spanned<Attribute_>) -> Vec<Span> {
+    let mut spans = Vec::new();
+    if let MetaItemKind::List(ref attribute_name, ref args) = attr.node.value.node {
+        if attribute_name == "condition" {
+            for arg in args {
+                if let MetaItemKind::NameValue(_, ref literal) = arg.node {
+                    spans.push(literal.span);
+                }
+            }
+        }
+    }
+    spans
+}
+
+/// Calls the expression parser on a condition written in an attribute.
 ///
 /// # Arguments:
 /// * `condition` - A user-submitted string
+/// * `span` - The span of the string literal the condition is written in.
 ///
 /// # Return:
-/// * If `condition` is valid, an Expression representing it.
+/// * If `condition` is valid, an Expression representing it, or else the parse or type error.
 ///
 /// # Remarks:
 /// * Current supported types: `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`, `bool`
-pub fn parse_condition(condition: &str) -> Expression {
+/// * Parse errors are reported at the token the parser stopped at, and type errors at the whole
+///   literal.
+///
+pub fn parse_condition(condition: &str, span: Span) -> Result<Expression, Error> {
     match expression_parser::parse_E1(condition) {
         Ok(e) => {
             match ty_check(&e) {
-                Ok(_) => return e,
-                Err(s) => rp_error!("{}", s),
+                Ok(_) => Ok(e),
+                Err(s) => rp_span_bail!(span, "{}", s),
             }
         },
-        Err(e) => rp_error!("Error parsing condition \"{}\": {:?}", condition, e)
+        Err(e) => {
+            let (lo, hi) = error_range(&e, condition.len());
+            let span = sub_span(condition, span, lo, hi);
+            match e {
+                ParseError::User{error: (_, _, message)} => {
+                    rp_span_bail!(span, "Error parsing condition \"{}\": {}", condition, message)
+                },
+                _ => rp_span_bail!(span, "Error parsing condition \"{}\": {:?}", condition, e),
+            }
+        }
+    }
+}
+
+// Returns the byte offsets in a condition of the part a parse error is found in
+fn error_range<T>(error: &ParseError<usize, T, (usize, usize, &str)>, len: usize)
+                  -> (usize, usize) {
+    match *error {
+        ParseError::InvalidToken{location} => (location, cmp::min(location + 1, len)),
+        ParseError::UnrecognizedToken{token: Some((lo, _, hi)), ..} => (lo, hi),
+        // The condition ended too soon
+        ParseError::UnrecognizedToken{token: None, ..} => (len, len),
+        ParseError::ExtraToken{token: (lo, _, hi)} => (lo, hi),
+        // An out of range literal
+        ParseError::User{error: (lo, hi, _)} => (lo, hi),
+    }
+}
+
+// Returns the span of the part of a condition between the given byte offsets, or the span of the
+// whole literal if the literal is not the condition in plain quotes (ie: it has escapes or is a
+// raw string), where offsets in the condition are not offsets in the source
+fn sub_span(condition: &str, span: Span, lo: usize, hi: usize) -> Span {
+    if (span.hi - span.lo).0 as usize != condition.len() + 2 {
+        return span;
+    }
+    Span {
+        lo: span.lo + BytePos(1 + lo as u32),
+        hi: span.lo + BytePos(1 + hi as u32),
+        expn_id: span.expn_id,
     }
 }
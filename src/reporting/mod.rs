@@ -9,37 +9,86 @@
 // except according to those terms.
 
 //! Prints user-facing errors and warnings.
+//!
+//! Errors abandon the function being verified. They are returned as the `Err` of a `Result` (see
+//! `rp_bail!` and `rp_span_bail!`), and `lib.rs` emits them through the compiler session with
+//! `emit`, so that the remaining functions are still verified. An error raised without a span is
+//! given one by the statement, block or condition it is returned through (see `Error::or_span`).
+
+use rustc::session::Session;
+use syntax::codemap::Span;
+
+/// An error that stops the verification of a function.
+#[derive(Debug)]
+pub struct Error {
+    /// Where the error is in the source, if known
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl Error {
+    /// Creates an error at a known location.
+    pub fn at(span: Span, message: String) -> Error {
+        Error { span: Some(span), message: message }
+    }
+
+    /// Attaches a span to the error if it was raised without one.
+    ///
+    /// # Remarks:
+    /// * Called as an error is returned through each enclosing statement or block, so the
+    ///   innermost span is kept.
+    ///
+    pub fn or_span(mut self, span: Span) -> Error {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+}
+
+// The checks of the expression module report their errors as strings, which `try!` turns into
+// errors without a span
+impl From<String> for Error {
+    fn from(message: String) -> Error {
+        Error { span: None, message: message }
+    }
+}
 
 // Warning macro
 macro_rules! rp_warn {
-    ($fmt:expr) => ({
-        let codemap = Rc::new(CodeMap::new());
-        let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(codemap.clone()));
-        let str = format!(concat!($fmt, "\n"));
-        handler.warn(&str);
-    });
-    ($fmt:expr, $($arg:tt)*) => ({
-        let codemap = Rc::new(CodeMap::new());
-        let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(codemap.clone()));
-        let str = format!(concat!($fmt, "\n"), $($arg)*);
-        handler.warn(&str);
+    ($sess:expr, $span:expr, $($arg:tt)*) => ({
+        $sess.span_warn($span, &format!($($arg)*));
     });
 }
 
-// Error macro
-macro_rules! rp_error {
-    ($fmt:expr) => ({
-        let codemap = Rc::new(CodeMap::new());
-        let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(codemap.clone()));
-        let str = concat!($fmt, "\n");
-        handler.err(&str);
-    process::exit(1);
+// Error macro, for functions that return a `Result<_, Error>`. The span is attached by the
+// statement, block or condition the error is returned through, see `Error::or_span`
+macro_rules! rp_bail {
+    ($($arg:tt)*) => ({
+        return Err(::reporting::Error {
+            span: None,
+            message: format!($($arg)*),
+        })
     });
-    ($fmt:expr, $($arg:tt)*) => ({
-        let codemap = Rc::new(CodeMap::new());
-        let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(codemap.clone()));
-        let str = format!(concat!($fmt, "\n"), $($arg)*);
-        handler.err(&str);
-    process::exit(1);
+}
+
+// Error macro, for errors whose span is known
+macro_rules! rp_span_bail {
+    ($span:expr, $($arg:tt)*) => ({
+        return Err(::reporting::Error {
+            span: Some($span),
+            message: format!($($arg)*),
+        })
     });
 }
+
+/// Reports an error through the compiler session.
+///
+/// # Arguments:
+/// * `sess` - The session of the crate being compiled.
+/// * `span` - The span to report the error at if it has none.
+/// * `error` - The error.
+///
+pub fn emit(sess: &Session, span: Span, error: Error) {
+    sess.span_err(error.span.unwrap_or(span), &error.message);
+}
@@ -8,10 +8,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! Interface between rustproof and libsmt(z3).
+//! Interface between rustproof and libsmt(z3), or another SMT solver.
 
 use std::fmt::Debug;
-use std::process;
+use std::collections::HashMap;
 
 use libsmt::backends::smtlib2::*;
 use libsmt::backends::backend::*;
@@ -20,24 +20,55 @@ use libsmt::theories::{bitvec, core};
 use libsmt::logics::qf_abv::*;
 use petgraph::graph::NodeIndex;
 
-use errors::{ColorConfig, Handler};
-use syntax::codemap::CodeMap;
-use std::rc::Rc;
-
 use expression::*;
 
-/// Invokes Z3 to check the satisfiability of a verification condition.
+mod model;
+mod script;
+mod solver;
+
+pub use self::model::{Counterexample, ModelValue, parse_model, format_counterexample, decode_value};
+pub use self::script::{gen_script, logic};
+pub use self::solver::{Solver, SOLVER_NAMES, SOLVER_VARIABLE};
+
+/// The outcome of checking a verification condition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum VerificationResult {
+    /// `P->WP` is always true.
+    Valid,
+    /// `P->WP` is false for the values of the variables in `counterexample`.
+    Invalid { counterexample: Counterexample },
+    /// The solver could not decide whether `P->WP` is always true.
+    Unknown,
+    /// The solver reported an error.
+    Error(String),
+}
+
+/// Invokes an SMT solver to check the satisfiability of a verification condition.
 ///
 /// # Arguments:
 /// * `vc` - A verification condition as an Expression.
-/// * `name` - The name of the function whose verification condition is being checked.
+/// * `names` - The source name of each variable in the verification condition that has one.
+/// * `solver` - The solver to check the verification condition with.
 /// * `debug` - A flag to enable/disable debug printing.
 ///
+/// # Return Value:
+/// * Returns whether the verification condition is valid, with a counterexample if it is not.
+///
 /// # Remarks:
 /// * Simply satisfying P->WP isn't enough; that will only tell us if P->WP is _sometimes true_. We
 /// * need to verify that !(P->WP) is *unsatisfiable* to determine that P->WP is _always true_.
+/// * Verification conditions with quantifiers cannot be checked in `QF_ABV`, and are written as
+///   a script in a quantified logic instead (see `script`). So are verification conditions with
+///   arrays, which libsmt's `QF_ABV` has no sorts for.
+/// * libsmt only runs Z3, so verification conditions checked with any other solver are written as
+///   a script too.
 ///
-pub fn gen_smtlib (vc: &Expression, name: String, debug: bool) {
+pub fn gen_smtlib (vc: &Expression, names: &HashMap<String, String>, solver: Solver, debug: bool)
+                   -> VerificationResult {
+    if solver != Solver::Z3 || contains_quantifier(vc) || contains_array(vc) {
+        return gen_scripted(vc, names, solver, debug);
+    }
+
     // Define an instance of Z3
     let mut z3: z3::Z3 = Default::default();
 
@@ -51,20 +82,142 @@ pub fn gen_smtlib (vc: &Expression, name: String, debug: bool) {
     let (_, check) = solver.solve(&mut z3, debug);
     match check {
         SMTRes::Sat(_, ref model) => {
-            println!(
-                "\nfn {}(..)\tVerification Condition is not valid.\n\n{}\n",
-                name,
-                model.clone().unwrap()
-            );
-        },
-        SMTRes::Unsat(..) => {
-            println!("\nfn {}(..)\tVerification Condition is valid.\n", name);
+            let mut variables = Vec::new();
+            collect_variables(vc, &mut variables);
+            let model = model.clone().unwrap_or(String::new());
+            VerificationResult::Invalid {
+                counterexample: parse_model(model.as_str(), &variables, names)
+            }
         },
-        SMTRes::Error(ref error, _) => {
-            println!("\nfn {}(..)\tError in Verification Condition Generation.\n{}\n", name, error);
+        SMTRes::Unsat(..) => VerificationResult::Valid,
+        // Z3 answers "unknown" when it gives up, which libsmt reports as an error
+        SMTRes::Error(ref error, _) if error.trim() == "unknown" => VerificationResult::Unknown,
+        SMTRes::Error(ref error, _) => VerificationResult::Error(error.clone()),
+    }
+}
+
+/// Checks that every variable in a verification condition has a type the solver can check.
+///
+/// # Arguments:
+/// * `vc` - A verification condition as an Expression.
+///
+/// # Return Value:
+/// * Returns an error naming the first variable whose type has no SMT-LIB sort.
+///
+pub fn check_sorts (vc: &Expression) -> Result<(), String> {
+    let mut variables = Vec::new();
+    collect_variables(vc, &mut variables);
+    for var in variables {
+        if script::gen_sort_of(&var.var_type).is_none() {
+            return Err(format!("Invalid or Unsupported type for variable: \"{}\" : \"{}\"",
+                               var.name, var.var_type));
         }
     }
+    Ok(())
+}
+
+// Checks a verification condition with a script, see `gen_smtlib`
+fn gen_scripted (vc: &Expression, names: &HashMap<String, String>, solver: Solver, debug: bool)
+                 -> VerificationResult {
+    let script = script::gen_script(vc, script::logic(vc));
+    if debug {
+        println!("{}", script);
+    }
 
+    match script::run_script(script.as_str(), solver) {
+        Ok((ref answer, ref model)) if answer == "sat" => {
+            let mut variables = Vec::new();
+            collect_variables(vc, &mut variables);
+            VerificationResult::Invalid {
+                counterexample: parse_model(model.as_str(), &variables, names)
+            }
+        },
+        Ok((ref answer, _)) if answer == "unsat" => VerificationResult::Valid,
+        Ok((ref answer, _)) if answer == "unknown" => VerificationResult::Unknown,
+        Ok((answer, rest)) => VerificationResult::Error(answer + "\n" + rest.as_str()),
+        Err(error) => VerificationResult::Error(error),
+    }
+}
+
+/// Prints the result of checking a verification condition.
+///
+/// # Arguments:
+/// * `name` - Identifies the function (and loop, if any) whose verification condition was
+///            checked.
+/// * `result` - The result of checking the verification condition.
+///
+pub fn print_result(name: &str, result: &VerificationResult) {
+    match *result {
+        VerificationResult::Valid => {
+            println!("\nfn {}\tVerification Condition is valid.\n", name);
+        },
+        VerificationResult::Invalid { ref counterexample } => {
+            println!("\nfn {}\tVerification Condition is not valid.\n\n{}\n",
+                     name,
+                     format_counterexample(counterexample));
+        },
+        VerificationResult::Unknown => {
+            println!("\nfn {}\tVerification Condition could not be decided.\n", name);
+        },
+        VerificationResult::Error(ref error) => {
+            println!("\nfn {}\tError in Verification Condition Generation.\n{}\n", name, error);
+        },
+    }
+}
+
+/// Prints the result of checking one obligation of a verification condition, below the result of
+/// the whole verification condition.
+///
+/// # Arguments:
+/// * `description` - What the obligation checks, and where.
+/// * `result` - The result of checking the obligation.
+///
+/// # Remarks:
+/// * Counterexamples are printed with the result of the whole verification condition; only the
+///   status of each obligation is printed here.
+///
+pub fn print_obligation_result(description: &str, result: &VerificationResult) {
+    let status = match *result {
+        VerificationResult::Valid => "holds",
+        VerificationResult::Invalid{..} => "can fail",
+        VerificationResult::Unknown => "could not be decided",
+        VerificationResult::Error(..) => "could not be checked",
+    };
+    println!("    {}\t{}", description, status);
+}
+
+/// Prints how many verification conditions of the crate had each result.
+///
+/// # Arguments:
+/// * `results` - The result of every verification condition checked, with the name it was
+///               printed under.
+///
+/// # Remarks:
+/// * Nothing is printed if no verification condition was checked.
+/// * Each verification condition that is not valid is listed by name, so that they can be found
+///   in a long output.
+///
+pub fn print_summary(results: &[(String, VerificationResult)]) {
+    if results.is_empty() {
+        return;
+    }
+    let (mut valid, mut invalid, mut unknown, mut error) = (0, 0, 0, 0);
+    for &(_, ref result) in results {
+        match *result {
+            VerificationResult::Valid => valid += 1,
+            VerificationResult::Invalid{..} => invalid += 1,
+            VerificationResult::Unknown => unknown += 1,
+            VerificationResult::Error(..) => error += 1,
+        }
+    }
+    println!("\nVerification summary: {} checked, {} valid, {} not valid, {} could not be decided, \
+              {} could not be checked.",
+             results.len(), valid, invalid, unknown, error);
+    for &(ref name, ref result) in results {
+        if *result != VerificationResult::Valid {
+            println!("    {}", name);
+        }
+    }
 }
 
 pub trait Pred2SMT {
@@ -81,6 +234,10 @@ impl Pred2SMT for SMTLib2<QF_ABV> {
     fn expr2smtlib (&mut self, vc: &Expression) -> Self::Idx {
         match *vc {
             Expression::BinaryExpression (ref b) => {
+                // Shifts of operands of different widths are done at the wider width
+                if let Some(shift) = widen_shift(b) {
+                    return self.expr2smtlib(&shift);
+                }
                 let l = self.expr2smtlib(b.left.as_ref());
                 let r = self.expr2smtlib(b.right.as_ref());
                 match b.op {
@@ -102,21 +259,50 @@ impl Pred2SMT for SMTLib2<QF_ABV> {
                         }
                     },
                     BinaryOperator::Modulo => {
-                        // Check for signedness
+                        // Check for signedness. Rust's remainder truncates, so its sign follows
+                        // the dividend as with bvsrem (bvsmod follows the divisor)
                         if determine_evaluation_type(vc).starts_with('i') {
-                            return self.assert(bitvec::OpCodes::BvSMod, &[l,r]);
+                            return self.assert(bitvec::OpCodes::BvSRem, &[l,r]);
                         } else {
                             return self.assert(bitvec::OpCodes::BvURem, &[l,r]);
                         }
                     },
+                    // The multiplication overflow predicates are written out as in `script`, so
+                    // they do not rely on Z3's own operators
                     BinaryOperator::SignedMultiplicationDoesNotOverflow => {
-                        return self.assert(bitvec::OpCodes::BvSMulDoesNotOverflow, &[l,r]);
+                        let left_type = determine_evaluation_type(b.left.as_ref());
+                        let width = integer_width(&left_type).unwrap();
+                        let product = gen_wide_product(self, l, r, width, true);
+                        let max = if width > 64 {
+                            gen_bv_const(self, i64::max_value() as u64, u64::max_value(), width)
+                        } else {
+                            gen_bv_const(self, 0, (1u64 << (width - 1)) - 1, width)
+                        };
+                        let max = self.assert(bitvec::OpCodes::SignExtend(width as u64), &[max]);
+                        return self.assert(bitvec::OpCodes::BvSLe, &[product, max]);
                     },
                     BinaryOperator::SignedMultiplicationDoesNotUnderflow => {
-                        return self.assert(bitvec::OpCodes::BvSMulDoesNotUnderflow, &[l,r]);
+                        let left_type = determine_evaluation_type(b.left.as_ref());
+                        let width = integer_width(&left_type).unwrap();
+                        let product = gen_wide_product(self, l, r, width, true);
+                        let min = if width > 64 {
+                            gen_bv_const(self, 1u64 << 63, 0, width)
+                        } else {
+                            gen_bv_const(self, 0, 1u64 << (width - 1), width)
+                        };
+                        let min = self.assert(bitvec::OpCodes::SignExtend(width as u64), &[min]);
+                        return self.assert(bitvec::OpCodes::BvSGe, &[product, min]);
                     },
                     BinaryOperator::UnsignedMultiplicationDoesNotOverflow => {
-                        return self.assert(bitvec::OpCodes::BvUMulDoesNotOverflow, &[l,r]);
+                        let left_type = determine_evaluation_type(b.left.as_ref());
+                        let width = integer_width(&left_type).unwrap();
+                        let product = gen_wide_product(self, l, r, width, false);
+                        // The upper half of the product is zero
+                        let upper = self.assert(bitvec::OpCodes::Extract(2 * width as u64 - 1,
+                                                                         width as u64),
+                                                &[product]);
+                        let zero = gen_bv_const(self, 0, 0, width);
+                        return self.assert(core::OpCodes::Cmp, &[upper, zero]);
                     },
                     BinaryOperator::BitwiseOr => {
                         if determine_evaluation_type(vc) == "bool" {
@@ -140,9 +326,11 @@ impl Pred2SMT for SMTLib2<QF_ABV> {
                         }
                     },
                     BinaryOperator::BitwiseLeftShift => {
+                        let r = self.expr2smtlib(&shift_amount(b.left.as_ref(), b.right.as_ref()));
                         return self.assert(bitvec::OpCodes::BvShl, &[l,r]);
                     },
                     BinaryOperator::BitwiseRightShift => {
+                        let r = self.expr2smtlib(&shift_amount(b.left.as_ref(), b.right.as_ref()));
                         // Check for signedness
                         if determine_evaluation_type(vc).starts_with('i') {
                             return self.assert(bitvec::OpCodes::BvAShr, &[l,r]);
@@ -217,6 +405,32 @@ impl Pred2SMT for SMTLib2<QF_ABV> {
                     },
                 }
             },
+            Expression::CastExpression (ref c) => {
+                let n = self.expr2smtlib(c.e.as_ref());
+                let from = determine_evaluation_type(c.e.as_ref());
+                let to_width = integer_width(&c.var_type).unwrap();
+                // true is 1 and false is 0
+                if from == "bool" {
+                    let one = gen_bv_const(self, 0, 1, to_width);
+                    let zero = gen_bv_const(self, 0, 0, to_width);
+                    return self.assert(core::OpCodes::ITE, &[n, one, zero]);
+                }
+                let from_width = integer_width(&from).unwrap();
+                if to_width < from_width {
+                    // Truncate to the lower bits
+                    return self.assert(bitvec::OpCodes::Extract(to_width as u64 - 1, 0), &[n]);
+                } else if to_width > from_width {
+                    // Extend according to the signedness of the source, as Rust does
+                    let extension = (to_width - from_width) as u64;
+                    if from.starts_with('i') {
+                        return self.assert(bitvec::OpCodes::SignExtend(extension), &[n]);
+                    } else {
+                        return self.assert(bitvec::OpCodes::ZeroExtend(extension), &[n]);
+                    }
+                }
+                // Casts between types of the same width keep the bits
+                return n;
+            },
             Expression::VariableMapping (ref v) => {
                 let sort = match v.var_type.as_ref() {
                     "bool" => bitvec::Sorts::Bool,
@@ -224,13 +438,11 @@ impl Pred2SMT for SMTLib2<QF_ABV> {
                     "i16" | "u16" => bitvec::Sorts::BitVector(16),
                     "i32" | "u32" => bitvec::Sorts::BitVector(32),
                     "i64" | "u64" => bitvec::Sorts::BitVector(64),
-                    _ => {
-                        rp_error!(
-                            "Invalid or Unsupported type for variable: \"{}\" : \"{}\"",
-                            v.name,
-                            v.var_type
-                        );
-                    },
+                    "i128" | "u128" => bitvec::Sorts::BitVector(128),
+                    "isize" | "usize" => bitvec::Sorts::BitVector(pointer_width() as usize),
+                    // The sorts of the variables are checked before the VC is generated, see
+                    // `check_sorts`
+                    _ => unreachable!(),
                 };
                 return self.new_var(Some(&v.name), sort);
             },
@@ -238,11 +450,48 @@ impl Pred2SMT for SMTLib2<QF_ABV> {
                 return self.new_const(core::OpCodes::Const(*b));
             },
             Expression::UnsignedBitVector (ref u) => {
-                return bv_const!(self, u.value, u.size as usize);
+                return gen_bv_const(self, u.high, u.value, u.size);
             },
             Expression::SignedBitVector (ref s) => {
-                return bv_const!(self, s.value as u64, s.size as usize);
-            }
+                return gen_bv_const(self, s.high as u64, s.value as u64, s.size);
+            },
+            // Variant checks are resolved to discriminant comparisons before the VC is generated
+            Expression::VariantCheck (_) => unreachable!(),
+            // gen_smtlib checks arrays with a script instead
+            Expression::ArrayIndex (_) | Expression::ArrayStore (_) => unreachable!(),
+            // QF_ABV has no quantifiers; gen_smtlib checks these with a script instead
+            Expression::QuantifiedExpression (_) => unreachable!(),
+            // Old expressions are resolved to snapshots before the VC is generated
+            Expression::Old (_) => unreachable!(),
         }
     }
 }
+
+// Multiplies two bitvectors of the given width after extending them to twice that width, where
+// their product cannot overflow
+fn gen_wide_product(solver: &mut SMTLib2<QF_ABV>,
+                    l: NodeIndex,
+                    r: NodeIndex,
+                    width: u8,
+                    signed: bool)
+                    -> NodeIndex {
+    let (l, r) = if signed {
+        (solver.assert(bitvec::OpCodes::SignExtend(width as u64), &[l]),
+         solver.assert(bitvec::OpCodes::SignExtend(width as u64), &[r]))
+    } else {
+        (solver.assert(bitvec::OpCodes::ZeroExtend(width as u64), &[l]),
+         solver.assert(bitvec::OpCodes::ZeroExtend(width as u64), &[r]))
+    };
+    solver.assert(bitvec::OpCodes::BvMul, &[l, r])
+}
+
+// Creates a bitvector constant from the upper and lower 64 bits of its value. libsmt's constants
+// hold 64 bits, so wider constants are the concatenation of their upper and lower halves.
+fn gen_bv_const(solver: &mut SMTLib2<QF_ABV>, high: u64, low: u64, size: u8) -> NodeIndex {
+    if size <= 64 {
+        return bv_const!(solver, low, size as usize);
+    }
+    let upper = bv_const!(solver, high, (size - 64) as usize);
+    let lower = bv_const!(solver, low, 64);
+    solver.assert(bitvec::OpCodes::Concat, &[upper, lower])
+}
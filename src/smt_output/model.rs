@@ -0,0 +1,237 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Turns the model Z3 gives for an invalid verification condition into a counterexample.
+
+use std::collections::{BTreeMap, HashMap};
+
+use expression::{VariableMappingData, OLD_SUFFIX, integer_width, parse_wide, format_wide,
+                 format_signed_wide};
+
+/// The value of a variable in a counterexample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelValue {
+    pub var_type: String,
+    /// The value in decimal, or `true`/`false`
+    pub value: String,
+}
+
+/// The values of a function's variables that make its verification condition false, keyed by
+/// their names in the source.
+pub type Counterexample = BTreeMap<String, ModelValue>;
+
+// An SMT-LIB s-expression
+enum SExpr {
+    Atom(String),
+    List(Vec<SExpr>),
+}
+
+/// Parses the model of an invalid verification condition into a counterexample.
+///
+/// # Arguments:
+/// * `model` - The model as printed by Z3, a list of `(define-fun <name> () <sort> <value>)`.
+/// * `variables` - The variables of the verification condition.
+/// * `names` - The source name of each variable in the verification condition that has one.
+///
+/// # Return Value:
+/// * Returns the value of each variable that has a source name.
+///
+/// # Remarks:
+/// * Fields keep their suffix, e.g. `var0.1` is reported as `y.1` if `var0` is `y` in the source.
+/// * Variables without a source name, such as MIR temporaries and the results of calls, are
+///   left out.
+///
+pub fn parse_model(model: &str,
+                   variables: &[VariableMappingData],
+                   names: &HashMap<String, String>) -> Counterexample {
+    let mut definitions = Vec::new();
+    let tokens = tokenize(model);
+    let mut position = 0;
+    while position < tokens.len() {
+        let sexpr = parse_sexpr(&tokens, &mut position);
+        find_definitions(&sexpr, &mut definitions);
+    }
+
+    let mut counterexample = Counterexample::new();
+    for (name, raw) in definitions {
+        let var = match variables.iter().find(|v| v.name == name) {
+            Some(var) => var,
+            None => continue,
+        };
+        let source_name = match source_name(&name, names) {
+            Some(source_name) => source_name,
+            None => continue,
+        };
+        let value = match decode_value(&raw, &var.var_type) {
+            Some(value) => value,
+            None => raw,
+        };
+        counterexample.insert(source_name, ModelValue {
+            var_type: var.var_type.clone(),
+            value: value
+        });
+    }
+    counterexample
+}
+
+/// Formats a counterexample with one `<name>: <type> = <value>` line per variable.
+///
+/// # Arguments:
+/// * `counterexample` - The counterexample to format.
+///
+pub fn format_counterexample(counterexample: &Counterexample) -> String {
+    let lines: Vec<String> = counterexample.iter().map(|(name, v)| {
+        format!("    {}: {} = {}", name, v.var_type, v.value)
+    }).collect();
+    lines.join("\n")
+}
+
+/// Decodes an SMT-LIB value according to the type of the variable holding it.
+///
+/// # Arguments:
+/// * `raw` - The value as printed by Z3: `true`, `false`, `#x..`, `#b..`, or `(_ bv.. ..)`.
+/// * `var_type` - The type of the variable, e.g. `"i32"`.
+///
+/// # Return Value:
+/// * Returns the value in decimal, or `None` if it cannot be decoded.
+///
+pub fn decode_value(raw: &str, var_type: &str) -> Option<String> {
+    if var_type == "bool" {
+        return match raw {
+            "true" | "false" => Some(raw.to_string()),
+            _ => None,
+        };
+    }
+
+    let width = match integer_width(var_type) {
+        Some(width) => width as u32,
+        None => return None,
+    };
+    // The upper and lower 64 bits of the value
+    let bits = if raw.starts_with("#x") {
+        parse_wide(&raw[2..], 16)
+    } else if raw.starts_with("#b") {
+        parse_wide(&raw[2..], 2)
+    } else if raw.starts_with("(_ bv") {
+        raw[5..].split_whitespace().next().and_then(|v| parse_wide(v, 10))
+    } else {
+        None
+    };
+
+    match (var_type.chars().next(), bits) {
+        (Some('u'), Some((high, low))) => Some(format_wide(high, low)),
+        // Sign extend from the width of the type
+        (Some('i'), Some((high, low))) if width > 64 => {
+            let shift = 128 - width;
+            Some(format_signed_wide(((high << shift) as i64) >> shift, low as i64))
+        },
+        (Some('i'), Some((_, low))) => {
+            let shift = 64 - width;
+            Some((((low << shift) as i64) >> shift).to_string())
+        },
+        _ => None,
+    }
+}
+
+// Maps the name of a variable in the verification condition to its name in the source
+fn source_name(name: &str, names: &HashMap<String, String>) -> Option<String> {
+    // Snapshots of entry values are reported as they are written in conditions
+    if name.ends_with(OLD_SUFFIX) {
+        let current = &name[..name.len() - OLD_SUFFIX.len()];
+        return source_name(current, names).map(|source| format!("old({})", source));
+    }
+    let (base, fields) = match name.find('.') {
+        Some(index) => (&name[..index], &name[index..]),
+        None => (name, ""),
+    };
+    names.get(base).map(|source| source.clone() + fields)
+}
+
+// Collects the name and value of every `define-fun` in an s-expression
+fn find_definitions(sexpr: &SExpr, definitions: &mut Vec<(String, String)>) {
+    if let SExpr::List(ref items) = *sexpr {
+        if items.len() == 5 {
+            if let (&SExpr::Atom(ref head), &SExpr::Atom(ref name)) = (&items[0], &items[1]) {
+                if head == "define-fun" {
+                    definitions.push((name.clone(), format_sexpr(&items[4])));
+                    return;
+                }
+            }
+        }
+        for item in items {
+            find_definitions(item, definitions);
+        }
+    }
+}
+
+// Prints an s-expression back out, e.g. `(_ bv5 32)`
+fn format_sexpr(sexpr: &SExpr) -> String {
+    match *sexpr {
+        SExpr::Atom(ref atom) => atom.clone(),
+        SExpr::List(ref items) => {
+            let items: Vec<String> = items.iter().map(format_sexpr).collect();
+            format!("({})", items.join(" "))
+        }
+    }
+}
+
+// Splits a model into parentheses and atoms. Quoted symbols (`|x.0|`) lose their quotes.
+fn tokenize(model: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in model.chars() {
+        if quoted {
+            if c == '|' {
+                quoted = false;
+            } else {
+                current.push(c);
+            }
+            continue;
+        }
+        match c {
+            '|' => quoted = true,
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(current.clone());
+                    current.clear();
+                }
+                tokens.push(c.to_string());
+            },
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(current.clone());
+                    current.clear();
+                }
+            },
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+// Parses the s-expression starting at `position`, leaving `position` just past it
+fn parse_sexpr(tokens: &[String], position: &mut usize) -> SExpr {
+    let token = tokens[*position].clone();
+    *position += 1;
+    if token != "(" {
+        return SExpr::Atom(token);
+    }
+    let mut items = Vec::new();
+    while *position < tokens.len() && tokens[*position] != ")" {
+        items.push(parse_sexpr(tokens, position));
+    }
+    // Skip the closing parenthesis
+    *position += 1;
+    SExpr::List(items)
+}
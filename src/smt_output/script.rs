@@ -0,0 +1,283 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Writes verification conditions as SMT-LIB scripts and runs them through a solver directly.
+//!
+//! libsmt's logics are quantifier-free, so verification conditions with quantifiers take this
+//! path instead, using a logic that allows them. So do verification conditions with arrays, and
+//! every verification condition checked with a solver other than Z3.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use expression::*;
+use super::solver::Solver;
+
+/// The logic used for verification conditions with quantifiers
+pub const QUANTIFIED_LOGIC: &'static str = "BV";
+
+/// The logic used for verification conditions with arrays
+pub const ARRAY_LOGIC: &'static str = "QF_ABV";
+
+/// The logic used for verification conditions with quantifiers and arrays
+pub const QUANTIFIED_ARRAY_LOGIC: &'static str = "AUFBV";
+
+/// Returns the logic a verification condition is checked in.
+///
+/// # Arguments:
+/// * `vc` - A verification condition as an Expression.
+///
+pub fn logic(vc: &Expression) -> &'static str {
+    match (contains_quantifier(vc), contains_array(vc)) {
+        (true, true) => QUANTIFIED_ARRAY_LOGIC,
+        (true, false) => QUANTIFIED_LOGIC,
+        _ => ARRAY_LOGIC,
+    }
+}
+
+/// Writes the script that checks a verification condition.
+///
+/// # Arguments:
+/// * `vc` - A verification condition as an Expression.
+/// * `logic` - The SMT-LIB logic to check the verification condition in.
+///
+/// # Remarks:
+/// * As with libsmt, the script asserts `!(P->WP)`, which is unsatisfiable when `P->WP` is valid.
+/// * The script only uses operators from the SMT-LIB standard, so any solver can run it. Models
+///   are requested before the logic is set, which some solvers require for `(get-model)`.
+///
+pub fn gen_script(vc: &Expression, logic: &str) -> String {
+    let mut script = format!("(set-option :produce-models true)\n(set-logic {})\n", logic);
+
+    let mut variables = Vec::new();
+    collect_variables(vc, &mut variables);
+    for var in variables {
+        script.push_str(&format!("(declare-fun |{}| () {})\n", var.name, gen_sort(&var)));
+    }
+
+    script.push_str(&format!("(assert (not {}))\n(check-sat)\n(get-model)\n", gen_term(vc)));
+    script
+}
+
+/// Runs a script through a solver.
+///
+/// # Arguments:
+/// * `script` - The script, as written by `gen_script`.
+/// * `solver` - The solver to run the script with.
+///
+/// # Return Value:
+/// * Returns the solver's answer to `(check-sat)` and everything it printed after it, or an
+///   error if the solver could not be run.
+///
+pub fn run_script(script: &str, solver: Solver) -> Result<(String, String), String> {
+    let (program, args) = solver.command();
+    let mut child = match Command::new(program).args(args)
+                                               .stdin(Stdio::piped())
+                                               .stdout(Stdio::piped())
+                                               .spawn() {
+        Ok(child) => child,
+        Err(e) => return Err(format!("Could not run {}: {}", program, e)),
+    };
+    if let Err(e) = child.stdin.as_mut().unwrap().write_all(script.as_bytes()) {
+        return Err(format!("Could not write to {}: {}", program, e));
+    }
+    let output = match child.wait_with_output() {
+        Ok(output) => String::from_utf8_lossy(&output.stdout).into_owned(),
+        Err(e) => return Err(format!("Could not read from {}: {}", program, e)),
+    };
+
+    let mut lines = output.splitn(2, '\n');
+    let answer = lines.next().unwrap_or("").trim().to_string();
+    let rest = lines.next().unwrap_or("").to_string();
+    Ok((answer, rest))
+}
+
+/// Returns the SMT-LIB sort of a type.
+///
+/// # Arguments:
+/// * `var_type` - The type of a variable in a verification condition.
+///
+/// # Return Value:
+/// * Returns the sort, or `None` if values of the type cannot be checked.
+///
+pub fn gen_sort_of(var_type: &str) -> Option<String> {
+    match var_type {
+        "bool" => Some("Bool".to_string()),
+        "i8" | "u8" => Some("(_ BitVec 8)".to_string()),
+        "i16" | "u16" => Some("(_ BitVec 16)".to_string()),
+        "i32" | "u32" => Some("(_ BitVec 32)".to_string()),
+        "i64" | "u64" => Some("(_ BitVec 64)".to_string()),
+        "i128" | "u128" => Some("(_ BitVec 128)".to_string()),
+        "isize" | "usize" => Some(format!("(_ BitVec {})", pointer_width())),
+        _ => None,
+    }
+}
+
+// Returns the SMT-LIB sort of a variable. The sorts of the variables in a verification condition
+// are checked before it is written, see `check_sorts`
+fn gen_sort(var: &VariableMappingData) -> String {
+    // Arrays and slices are indexed by usize
+    if let Some(e_type) = element_type(&var.var_type) {
+        let element = VariableMappingData { name: var.name.clone(), var_type: e_type };
+        return format!("(Array (_ BitVec {}) {})", pointer_width(), gen_sort(&element));
+    }
+    match gen_sort_of(&var.var_type) {
+        Some(sort) => sort,
+        None => unreachable!(),
+    }
+}
+
+// Returns the term of a multiplication overflow predicate, or None for any other operator. The
+// operands are extended to twice their width, where their product cannot overflow, and the product
+// is compared with the bounds of the original width
+fn gen_mul_check(op: &BinaryOperator, left: &Expression, l: &str, r: &str) -> Option<String> {
+    let width = match *op {
+        BinaryOperator::SignedMultiplicationDoesNotOverflow
+        | BinaryOperator::SignedMultiplicationDoesNotUnderflow
+        | BinaryOperator::UnsignedMultiplicationDoesNotOverflow => {
+            integer_width(&determine_evaluation_type(left)).unwrap() as u32
+        },
+        _ => return None,
+    };
+    let extend = match *op {
+        BinaryOperator::UnsignedMultiplicationDoesNotOverflow => "zero_extend",
+        _ => "sign_extend",
+    };
+    let product = format!("(bvmul ((_ {0} {1}) {2}) ((_ {0} {1}) {3}))", extend, width, l, r);
+    // The bounds of the signed type, extended in the same way
+    let (min, max) = if width > 64 {
+        (format_wide(1 << 63, 0), format_wide(i64::max_value() as u64, u64::max_value()))
+    } else {
+        ((1u64 << (width - 1)).to_string(), ((1u64 << (width - 1)) - 1).to_string())
+    };
+    Some(match *op {
+        BinaryOperator::SignedMultiplicationDoesNotOverflow => {
+            format!("(bvsle {} ((_ sign_extend {}) (_ bv{} {})))", product, width, max, width)
+        },
+        BinaryOperator::SignedMultiplicationDoesNotUnderflow => {
+            format!("(bvsge {} ((_ sign_extend {}) (_ bv{} {})))", product, width, min, width)
+        },
+        // The upper half of the product is zero
+        _ => format!("(= ((_ extract {} {}) {}) (_ bv0 {}))", 2 * width - 1, width, product, width),
+    })
+}
+
+// Returns the SMT-LIB term of an Expression, with the same meaning as `Pred2SMT::expr2smtlib`
+fn gen_term(vc: &Expression) -> String {
+    match *vc {
+        Expression::BinaryExpression (ref b) => {
+            // Shifts of operands of different widths are done at the wider width
+            if let Some(shift) = widen_shift(b) {
+                return gen_term(&shift);
+            }
+            let l = gen_term(b.left.as_ref());
+            let r = match b.op {
+                // The amount of a shift has the width of the value shifted
+                BinaryOperator::BitwiseLeftShift | BinaryOperator::BitwiseRightShift => {
+                    gen_term(&shift_amount(b.left.as_ref(), b.right.as_ref()))
+                },
+                _ => gen_term(b.right.as_ref()),
+            };
+            let signed = determine_evaluation_type(b.left.as_ref()).starts_with('i');
+            // Z3's bvsmul_noovfl and friends are not standard, so these are written out
+            if let Some(check) = gen_mul_check(&b.op, b.left.as_ref(), &l, &r) {
+                return check;
+            }
+            let boolean = determine_evaluation_type(b.left.as_ref()) == "bool";
+            let op = match b.op {
+                BinaryOperator::Addition => "bvadd",
+                BinaryOperator::Subtraction => "bvsub",
+                BinaryOperator::Multiplication => "bvmul",
+                BinaryOperator::Division => if signed { "bvsdiv" } else { "bvudiv" },
+                BinaryOperator::Modulo => if signed { "bvsrem" } else { "bvurem" },
+                BinaryOperator::SignedMultiplicationDoesNotOverflow
+                | BinaryOperator::SignedMultiplicationDoesNotUnderflow
+                | BinaryOperator::UnsignedMultiplicationDoesNotOverflow => unreachable!(),
+                BinaryOperator::BitwiseOr => if boolean { "or" } else { "bvor" },
+                BinaryOperator::BitwiseAnd => if boolean { "and" } else { "bvand" },
+                BinaryOperator::BitwiseXor => if boolean { "xor" } else { "bvxor" },
+                BinaryOperator::BitwiseLeftShift => "bvshl",
+                BinaryOperator::BitwiseRightShift => if signed { "bvashr" } else { "bvlshr" },
+                BinaryOperator::LessThan => if signed { "bvslt" } else { "bvult" },
+                BinaryOperator::LessThanOrEqual => if signed { "bvsle" } else { "bvule" },
+                BinaryOperator::GreaterThan => if signed { "bvsgt" } else { "bvugt" },
+                BinaryOperator::GreaterThanOrEqual => if signed { "bvsge" } else { "bvuge" },
+                BinaryOperator::Equal | BinaryOperator::BiImplication => "=",
+                BinaryOperator::NotEqual => return format!("(not (= {} {}))", l, r),
+                BinaryOperator::And => "and",
+                BinaryOperator::Or => "or",
+                BinaryOperator::Xor => "xor",
+                BinaryOperator::Implication => "=>",
+            };
+            format!("({} {} {})", op, l, r)
+        },
+        Expression::UnaryExpression (ref u) => {
+            let op = match u.op {
+                UnaryOperator::Negation => "bvneg",
+                UnaryOperator::BitwiseNot => "bvnot",
+                UnaryOperator::Not => "not",
+            };
+            format!("({} {})", op, gen_term(u.e.as_ref()))
+        },
+        Expression::CastExpression (ref c) => {
+            let e = gen_term(c.e.as_ref());
+            let from = determine_evaluation_type(c.e.as_ref());
+            let to_width = integer_width(&c.var_type).unwrap();
+            if from == "bool" {
+                return format!("(ite {} (_ bv1 {}) (_ bv0 {}))", e, to_width, to_width);
+            }
+            let from_width = integer_width(&from).unwrap();
+            if to_width < from_width {
+                format!("((_ extract {} 0) {})", to_width - 1, e)
+            } else if to_width > from_width {
+                let extend = if from.starts_with('i') { "sign_extend" } else { "zero_extend" };
+                format!("((_ {} {}) {})", extend, to_width - from_width, e)
+            } else {
+                e
+            }
+        },
+        Expression::ArrayIndex (ref a) => {
+            format!("(select {} {})", gen_term(a.array.as_ref()), gen_term(a.index.as_ref()))
+        },
+        Expression::ArrayStore (ref a) => {
+            format!("(store {} {} {})",
+                    gen_term(a.array.as_ref()),
+                    gen_term(a.index.as_ref()),
+                    gen_term(a.value.as_ref()))
+        },
+        Expression::VariableMapping (ref v) => format!("|{}|", v.name),
+        Expression::BooleanLiteral (ref b) => b.to_string(),
+        Expression::UnsignedBitVector (ref u) => {
+            format!("(_ bv{} {})", format_wide(u.high, u.value), u.size)
+        },
+        Expression::SignedBitVector (ref s) => {
+            // Two's complement, truncated to the size of the bitvector
+            let bits = if s.size > 64 {
+                format_wide(s.high as u64, s.value as u64)
+            } else if s.size == 64 {
+                (s.value as u64).to_string()
+            } else {
+                ((s.value as u64) & ((1u64 << s.size) - 1)).to_string()
+            };
+            format!("(_ bv{} {})", bits, s.size)
+        },
+        // Variant checks are resolved to discriminant comparisons before the VC is generated
+        Expression::VariantCheck (_) => unreachable!(),
+        Expression::QuantifiedExpression (ref q) => {
+            let quantifier = match q.quantifier {
+                Quantifier::ForAll => "forall",
+                Quantifier::Exists => "exists",
+            };
+            format!("({} ((|{}| {})) {})", quantifier, q.var.name, gen_sort(&q.var), gen_term(&q.e))
+        },
+        // Old expressions are resolved to snapshots before the VC is generated
+        Expression::Old (_) => unreachable!(),
+    }
+}
@@ -0,0 +1,98 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The SMT solvers verification conditions can be checked with.
+//!
+//! Every solver is run as a child process that reads an SMT-LIB2 script from its standard input,
+//! so any of them that is installed and in the PATH can be used.
+
+/// The environment variable that chooses the solver when the `solver` plugin argument is not
+/// given.
+pub const SOLVER_VARIABLE: &'static str = "RUSTPROOF_SOLVER";
+
+/// The names the solvers are chosen by, in the order they are listed in error messages.
+pub const SOLVER_NAMES: &'static [&'static str] =
+    &["z3", "cvc4", "cvc5", "yices", "boolector", "bitwuzla"];
+
+/// An SMT solver that accepts SMT-LIB2 scripts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Solver {
+    Z3,
+    Cvc4,
+    Cvc5,
+    Yices,
+    Boolector,
+    Bitwuzla,
+}
+
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver::Z3
+    }
+}
+
+impl Solver {
+    /// Looks up a solver by name.
+    ///
+    /// # Arguments:
+    /// * `name` - One of `SOLVER_NAMES`, in any case.
+    ///
+    /// # Return Value:
+    /// * Returns the solver, or `None` if there is no solver with that name.
+    ///
+    pub fn from_name(name: &str) -> Option<Solver> {
+        match name.trim().to_lowercase().as_str() {
+            "z3" => Some(Solver::Z3),
+            "cvc4" => Some(Solver::Cvc4),
+            "cvc5" => Some(Solver::Cvc5),
+            "yices" => Some(Solver::Yices),
+            "boolector" => Some(Solver::Boolector),
+            "bitwuzla" => Some(Solver::Bitwuzla),
+            _ => None,
+        }
+    }
+
+    /// Returns the name the solver is chosen by.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Solver::Z3 => "z3",
+            Solver::Cvc4 => "cvc4",
+            Solver::Cvc5 => "cvc5",
+            Solver::Yices => "yices",
+            Solver::Boolector => "boolector",
+            Solver::Bitwuzla => "bitwuzla",
+        }
+    }
+
+    /// Returns whether the solver can check verification conditions with quantifiers, which are
+    /// written in the `BV` and `AUFBV` logics.
+    pub fn supports_quantifiers(&self) -> bool {
+        match *self {
+            Solver::Z3 | Solver::Cvc4 | Solver::Cvc5 | Solver::Bitwuzla => true,
+            // Both only support quantifier-free bitvector logics
+            Solver::Yices | Solver::Boolector => false,
+        }
+    }
+
+    /// Returns the program that runs the solver, and the arguments that make it read an
+    /// SMT-LIB2 script from its standard input and print a model when asked for one.
+    pub fn command(&self) -> (&'static str, &'static [&'static str]) {
+        match *self {
+            Solver::Z3 => ("z3", &["-in", "-smt2"]),
+            Solver::Cvc4 => ("cvc4", &["--lang", "smt2"]),
+            Solver::Cvc5 => ("cvc5", &["--lang", "smt2"]),
+            // Yices reads SMT-LIB2 through its own executable
+            Solver::Yices => ("yices-smt2", &[]),
+            // Boolector only answers (get-model) with model generation enabled
+            Solver::Boolector => ("boolector", &["--smt2", "-m"]),
+            Solver::Bitwuzla => ("bitwuzla", &["--lang", "smt2"]),
+        }
+    }
+}
@@ -11,4 +11,7 @@
 
 mod test_reporting;
 mod test_expression;
+mod test_parser;
+mod test_model;
+mod test_script;
 mod system_tests;
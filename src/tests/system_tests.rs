@@ -89,6 +89,106 @@ fn test_assertion_examples(){
     assert!(test_example_file("test_assert"));
 }
 
+// Test examples for loop invariant examples
+#[test]
+fn test_loop_examples(){
+    assert!(test_example_file("test_loops"));
+}
+
+// Test examples for match examples
+#[test]
+fn test_match_examples(){
+    assert!(test_example_file("test_match"));
+}
+
+// Test examples for function call examples
+#[test]
+fn test_call_examples(){
+    assert!(test_example_file("test_calls"));
+}
+
+// Test examples for quantifier examples
+#[test]
+fn test_quantifier_examples(){
+    assert!(test_example_file("test_quantifiers"));
+}
+
+// Test examples for old expressions
+#[test]
+fn test_old_examples(){
+    assert!(test_example_file("test_old"));
+}
+
+// Test examples for isize and usize
+#[test]
+fn test_usize_examples(){
+    assert!(test_example_file("test_usize"));
+}
+
+// Test examples for casts
+#[test]
+fn test_cast_examples(){
+    assert!(test_example_file("test_casts"));
+}
+
+// Test examples for arrays and slices
+#[test]
+fn test_array_examples(){
+    assert!(test_example_file("test_arrays"));
+}
+
+// Test examples for structs
+#[test]
+fn test_struct_examples(){
+    assert!(test_example_file("test_structs"));
+}
+
+// Test examples for references
+#[test]
+fn test_reference_examples(){
+    assert!(test_example_file("test_references"));
+}
+
+// Test examples for tuples
+#[test]
+fn test_tuple_examples(){
+    assert!(test_example_file("test_tuples"));
+}
+
+// Test examples for drops and unreachable code
+#[test]
+fn test_drop_examples(){
+    assert!(test_example_file("test_drops"));
+}
+
+// Test examples for shifts
+#[test]
+fn test_shift_examples(){
+    assert!(test_example_file("test_shifts"));
+}
+
+// Test examples for remainders and negation
+#[test]
+fn test_remainder_examples(){
+    assert!(test_example_file("test_remainder"));
+}
+
+// Test examples for unsupported cycles
+// Loops without invariants and recursive calls must be reported as compile errors
+#[test]
+fn test_cycle_examples(){
+    assert!(!test_example_file("test_loop_no_invariant"));
+    assert!(!test_example_file("test_recursion"));
+}
+
+// Test examples for the deny plugin argument
+// Invalid verification conditions must be reported as compile errors
+#[test]
+fn test_deny_examples(){
+    assert!(test_example_file("test_deny_valid"));
+    assert!(!test_example_file("test_deny_invalid"));
+}
+
 #[test]
 #[should_panic]
 fn test_system_test_validity() {
@@ -9,6 +9,9 @@
 // except according to those terms.
 
 use expression::*;
+use parser::parse_condition;
+use syntax::codemap::DUMMY_SP;
+use std::collections::HashMap;
 
 #[test]
 fn test_all_substitute_binary_operators() {
@@ -236,3 +239,171 @@ fn variable_mapping_data_equality() {
     assert!(var1 != var6);
     assert!(var6 == var6);
 }
+
+#[test]
+fn resolve_variant_checks_to_discriminant() {
+    let mut enum_variants = HashMap::new();
+    enum_variants.insert("Option<u32>".to_string(), vec!["None".to_string(), "Some".to_string()]);
+    let x: VariableMappingData = VariableMappingData {
+        name: "x".to_string(),
+        var_type: "Option<u32>".to_string()
+    };
+    let mut p: Expression = Expression::UnaryExpression( UnaryExpressionData {
+        op: UnaryOperator::Not,
+        e: Box::new(Expression::VariantCheck( VariantCheckData {
+            var: x.clone(),
+            variant: "Some".to_string()
+        }))
+    });
+
+    let correct_result: Expression = Expression::UnaryExpression( UnaryExpressionData {
+        op: UnaryOperator::Not,
+        e: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::Equal,
+            left: Box::new(Expression::VariableMapping( VariableMappingData {
+                name: "x.discr".to_string(),
+                var_type: "u64".to_string()
+            })),
+            right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
+                size: 64,
+                value: 1,
+                high: 0
+            }))
+        }))
+    });
+    assert_eq!(resolve_variant_checks(&mut p, &enum_variants), Ok(true));
+    assert_eq!(p, correct_result);
+}
+
+#[test]
+fn resolve_variant_checks_unknown_variant() {
+    let mut enum_variants = HashMap::new();
+    enum_variants.insert("Option<u32>".to_string(), vec!["None".to_string(), "Some".to_string()]);
+    let mut p: Expression = Expression::VariantCheck( VariantCheckData {
+        var: VariableMappingData {
+            name: "x".to_string(),
+            var_type: "Option<u32>".to_string()
+        },
+        variant: "Ok".to_string()
+    });
+    assert!(resolve_variant_checks(&mut p, &enum_variants).is_err());
+}
+
+#[test]
+fn substitute_variable_fields_renames_fields() {
+    let field = |name: &str, var_type: &str| Expression::VariableMapping( VariableMappingData {
+        name: name.to_string(),
+        var_type: var_type.to_string()
+    });
+    let mut p: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::And,
+        left: Box::new(field("var1.discr", "u64")),
+        right: Box::new(field("var10.Some.0", "u32"))
+    });
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::And,
+        left: Box::new(field("x.discr", "u64")),
+        right: Box::new(field("var10.Some.0", "u32"))
+    });
+    substitute_variable_fields(&mut p, "var1", "x");
+    assert_eq!(p, correct_result);
+}
+
+#[test]
+fn substitute_variable_with_expression_avoids_capture() {
+    let var = |name: &str| Expression::VariableMapping( VariableMappingData {
+        name: name.to_string(),
+        var_type: "u32".to_string()
+    });
+    let quantified = |bound: &str, e: Expression| Expression::QuantifiedExpression(
+        QuantifiedExpressionData {
+            quantifier: Quantifier::ForAll,
+            var: VariableMappingData { name: bound.to_string(), var_type: "u32".to_string() },
+            e: Box::new(e)
+        }
+    );
+    let less_than = |l: Expression, r: Expression| Expression::BinaryExpression(
+        BinaryExpressionData { op: BinaryOperator::LessThan, left: Box::new(l), right: Box::new(r) }
+    );
+    let x = VariableMappingData { name: "x".to_string(), var_type: "u32".to_string() };
+
+    // forall i. i < x, with x replaced by i, must not capture i
+    let mut p: Expression = quantified("i", less_than(var("i"), var("x")));
+    substitute_variable_with_expression(&mut p, &x, &var("i"));
+    assert_eq!(p, quantified("i'", less_than(var("i'"), var("i"))));
+
+    // forall x. x < i, with x replaced by i, binds x, so nothing is replaced
+    let mut p: Expression = quantified("x", less_than(var("x"), var("i")));
+    substitute_variable_with_expression(&mut p, &x, &var("i"));
+    assert_eq!(p, quantified("x", less_than(var("x"), var("i"))));
+}
+
+#[test]
+fn resolve_old_expressions_snapshots_entry_values() {
+    let var = |name: &str| Expression::VariableMapping( VariableMappingData {
+        name: name.to_string(),
+        var_type: "u32".to_string()
+    });
+    let plus = |l: Expression, r: Expression| Expression::BinaryExpression(
+        BinaryExpressionData { op: BinaryOperator::Addition, left: Box::new(l), right: Box::new(r) }
+    );
+
+    // x + old(x + y) becomes x + (x@old + y@old)
+    let mut p: Expression = plus(var("x"), Expression::Old(Box::new(plus(var("x"), var("y")))));
+    assert_eq!(resolve_old_expressions(&mut p, OLD_SUFFIX), Ok(true));
+    assert_eq!(p, plus(var("x"), plus(var("x@old"), var("y@old"))));
+
+    // On entry, the snapshots are the current values
+    restore_old_values(&mut p);
+    assert_eq!(p, plus(var("x"), plus(var("x"), var("y"))));
+
+    // The return value has no entry value
+    let mut p: Expression = Expression::Old(Box::new(var("return")));
+    assert!(resolve_old_expressions(&mut p, OLD_SUFFIX).is_err());
+}
+
+#[test]
+fn pointer_sized_types_have_pointer_width() {
+    assert_eq!(integer_width("usize"), Some(64));
+    set_pointer_width(32);
+    assert_eq!(integer_width("isize"), Some(32));
+    set_pointer_width(64);
+    // A usize literal keeps its type
+    let literal = pointer_sized(Expression::UnsignedBitVector( UnsignedBitVectorData {
+        size: 64,
+        value: 1,
+        high: 0
+    }), "usize");
+    assert_eq!(determine_evaluation_type(&literal), "usize");
+}
+
+#[test]
+fn wide_integers_round_trip() {
+    assert_eq!(parse_wide("340282366920938463463374607431768211455", 10),
+               Some((u64::max_value(), u64::max_value())));
+    assert_eq!(parse_wide("340282366920938463463374607431768211456", 10), None);
+    assert_eq!(parse_wide("10000000000000000", 16), Some((1, 0)));
+    assert_eq!(parse_wide("12a", 10), None);
+    assert_eq!(format_wide(5421010862, 7886392056514347008), "100000000000000000000000000000");
+    assert_eq!(format_wide(0, 42), "42");
+    assert_eq!(format_signed_wide(-1, -123), "-123");
+    assert_eq!(parse_i128("-170141183460469231731687303715884105728"),
+               Some((i64::min_value(), 0)));
+    assert_eq!(parse_i128("170141183460469231731687303715884105728"), None);
+}
+
+#[test]
+fn element_type_and_array_length() {
+    assert_eq!(element_type("[u32; 4]"), Some("u32".to_string()));
+    assert_eq!(element_type("[[u8; 2]; 3]"), Some("[u8; 2]".to_string()));
+    assert_eq!(element_type("[i64]"), Some("i64".to_string()));
+    assert_eq!(element_type("u32"), None);
+    assert_eq!(array_length("[[u8; 2]; 3]"), Some(3));
+    assert_eq!(array_length("[u8]"), None);
+}
+
+#[test]
+fn ty_check_shift_amounts_of_any_integer_type() {
+    assert!(ty_check(&parse_condition("(x: u8 << n: i32) == 0u8", DUMMY_SP).unwrap()).is_ok());
+    assert!(ty_check(&parse_condition("(x: i64 >> n: u8) == 0i64", DUMMY_SP).unwrap()).is_ok());
+}
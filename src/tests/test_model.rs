@@ -0,0 +1,65 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use expression::*;
+use smt_output::*;
+use std::collections::HashMap;
+
+fn var(name: &str, var_type: &str) -> VariableMappingData {
+    VariableMappingData{ name: name.to_string(), var_type: var_type.to_string() }
+}
+
+#[test]
+fn decode_value_signed() {
+    assert_eq!(decode_value("#x7ffffffc", "i32"), Some("2147483644".to_string()));
+    assert_eq!(decode_value("#xfffffffe", "i32"), Some("-2".to_string()));
+    assert_eq!(decode_value("#b10000000", "i8"), Some("-128".to_string()));
+    assert_eq!(decode_value("#xffffffffffffffffffffffffffffff85", "i128"),
+               Some("-123".to_string()));
+}
+
+#[test]
+fn decode_value_pointer_sized() {
+    set_pointer_width(32);
+    assert_eq!(decode_value("#xfffffffe", "isize"), Some("-2".to_string()));
+    set_pointer_width(64);
+    assert_eq!(decode_value("#xfffffffffffffffe", "usize"),
+               Some("18446744073709551614".to_string()));
+}
+
+#[test]
+fn decode_value_unsigned() {
+    assert_eq!(decode_value("#xfffffffe", "u32"), Some("4294967294".to_string()));
+    assert_eq!(decode_value("(_ bv5 64)", "u64"), Some("5".to_string()));
+    assert_eq!(decode_value("true", "bool"), Some("true".to_string()));
+    assert_eq!(decode_value("(_ bv100000000000000000000000000000 128)", "u128"),
+               Some("100000000000000000000000000000".to_string()));
+}
+
+#[test]
+fn parse_model_maps_names() {
+    let model = "(model\n  (define-fun x () (_ BitVec 32)\n    #x7ffffffc)\n  \
+                 (define-fun tmp0 () (_ BitVec 32)\n    #x00000000)\n  \
+                 (define-fun |var0.1| () (_ BitVec 8)\n    #xff)\n)";
+    let variables = vec![var("x", "i32"), var("tmp0", "i32"), var("var0.1", "u8")];
+    let mut names = HashMap::new();
+    names.insert("x".to_string(), "x".to_string());
+    names.insert("var0".to_string(), "y".to_string());
+
+    let counterexample = parse_model(model, &variables, &names);
+    // Temps have no source name and are left out
+    assert_eq!(counterexample.len(), 2);
+    assert_eq!(counterexample["x"], ModelValue{ var_type: "i32".to_string(),
+                                                value: "2147483644".to_string() });
+    assert_eq!(counterexample["y.1"], ModelValue{ var_type: "u8".to_string(),
+                                                  value: "255".to_string() });
+    assert_eq!(format_counterexample(&counterexample),
+               "    x: i32 = 2147483644\n    y.1: u8 = 255");
+}
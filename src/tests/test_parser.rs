@@ -0,0 +1,369 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use expression::*;
+use parser::parse_condition;
+use syntax::codemap::{mk_sp, BytePos, DUMMY_SP};
+
+#[test]
+fn parse_variant_check() {
+    let correct_result: Expression = Expression::VariantCheck( VariantCheckData {
+        var: VariableMappingData {
+            name: "x".to_string(),
+            var_type: "Option<u32>".to_string()
+        },
+        variant: "Some".to_string()
+    });
+    assert_eq!(parse_condition("x: Option<u32> is Some", DUMMY_SP).unwrap(), correct_result);
+}
+
+#[test]
+fn parse_variant_check_multiple_type_arguments() {
+    let correct_result: Expression = Expression::VariantCheck( VariantCheckData {
+        var: VariableMappingData {
+            name: "return".to_string(),
+            var_type: "Result<Option<u8>, bool>".to_string()
+        },
+        variant: "Err".to_string()
+    });
+    assert_eq!(parse_condition("return: Result<Option<u8> , bool> is Err", DUMMY_SP).unwrap(),
+               correct_result);
+}
+
+#[test]
+fn parse_variant_check_in_expression() {
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::And,
+        left: Box::new(Expression::VariantCheck( VariantCheckData {
+            var: VariableMappingData {
+                name: "c".to_string(),
+                var_type: "Color".to_string()
+            },
+            variant: "Red".to_string()
+        })),
+        right: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::LessThan,
+            left: Box::new(Expression::VariableMapping( VariableMappingData {
+                name: "x".to_string(),
+                var_type: "u32".to_string()
+            })),
+            right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
+                size: 32,
+                value: 5,
+                high: 0
+            }))
+        }))
+    });
+    assert_eq!(parse_condition("(c: Color is Red) && (x: u32 < 5u32)", DUMMY_SP).unwrap(),
+               correct_result);
+}
+
+// A condition literal whose opening quote is at byte 10
+fn literal_span(condition: &str) -> ::syntax::codemap::Span {
+    mk_sp(BytePos(10), BytePos(12 + condition.len() as u32))
+}
+
+#[test]
+fn parse_error_is_reported_at_token() {
+    let condition = "x: u32 < < 5u32";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(20), BytePos(21))));
+}
+
+#[test]
+fn parse_error_is_reported_at_invalid_character() {
+    let condition = "x: u32 # 5u32";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(18), BytePos(19))));
+}
+
+#[test]
+fn parse_error_is_reported_at_end_of_condition() {
+    let condition = "x: u32 <";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(19), BytePos(19))));
+}
+
+#[test]
+fn parse_error_in_escaped_literal_is_reported_at_literal() {
+    // The literal is longer than the condition, so offsets in the condition are not offsets in
+    // the source
+    let span = mk_sp(BytePos(10), BytePos(30));
+    let error = parse_condition("x: u32 < < 5u32", span).unwrap_err();
+    assert_eq!(error.span, Some(span));
+}
+
+#[test]
+fn type_error_is_reported_at_literal() {
+    let condition = "x: u32 == 5u8";
+    let span = literal_span(condition);
+    let error = parse_condition(condition, span).unwrap_err();
+    assert_eq!(error.span, Some(span));
+}
+
+#[test]
+fn out_of_range_literal_is_reported_at_literal() {
+    let condition = "x: u8 == 300u8";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(20), BytePos(25))));
+    assert!(error.message.contains("Out of range value for u8 type"));
+    assert!(parse_condition("x: i8 == -129i8", DUMMY_SP).is_err());
+    assert!(parse_condition("x: u64 == 18446744073709551616u64", DUMMY_SP).is_err());
+}
+
+#[test]
+fn keyword_is_reported_at_identifier() {
+    let condition = "match: u32 == 0u32";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(11), BytePos(16))));
+    assert!(error.message.contains("Use of reserved keyword as identifier"));
+}
+
+#[test]
+fn parse_quantifier() {
+    let correct_result: Expression = Expression::QuantifiedExpression( QuantifiedExpressionData {
+        quantifier: Quantifier::Exists,
+        var: VariableMappingData {
+            name: "i".to_string(),
+            var_type: "u8".to_string()
+        },
+        e: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::Equal,
+            left: Box::new(Expression::VariableMapping( VariableMappingData {
+                name: "i".to_string(),
+                var_type: "u8".to_string()
+            })),
+            right: Box::new(Expression::VariableMapping( VariableMappingData {
+                name: "x".to_string(),
+                var_type: "u8".to_string()
+            }))
+        }))
+    });
+    assert_eq!(parse_condition("exists(i: u8, i: u8 == x: u8)", DUMMY_SP).unwrap(), correct_result);
+}
+
+#[test]
+fn parse_quantifier_mismatched_bound_variable_type() {
+    assert!(parse_condition("forall(i: u8, i: u16 == 0u16)", DUMMY_SP).is_err());
+}
+
+#[test]
+fn parse_old() {
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::Equal,
+        left: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "return".to_string(),
+            var_type: "u32".to_string()
+        })),
+        right: Box::new(Expression::Old(Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "x".to_string(),
+            var_type: "u32".to_string()
+        }))))
+    });
+    assert_eq!(parse_condition("return: u32 == old(x: u32)", DUMMY_SP).unwrap(), correct_result);
+}
+
+#[test]
+fn parse_usize() {
+    // isize and usize literals are pointer-sized bitvectors, 64 bits unless the width is set
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::LessThan,
+        left: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "n".to_string(),
+            var_type: "usize".to_string()
+        })),
+        right: Box::new(pointer_sized(Expression::UnsignedBitVector( UnsignedBitVectorData {
+            size: 64,
+            value: 10,
+            high: 0
+        }), "usize"))
+    });
+    assert_eq!(parse_condition("n: usize < 10usize", DUMMY_SP).unwrap(), correct_result);
+    assert_eq!(parse_condition("isize::MIN == -9223372036854775808isize", DUMMY_SP).unwrap(),
+               parse_condition("isize::MIN == isize::MIN", DUMMY_SP).unwrap());
+}
+
+#[test]
+fn parse_usize_is_not_u64() {
+    // usize is a type of its own, even where it has the width of u64
+    assert!(parse_condition("x: usize == 1u64", DUMMY_SP).is_err());
+    assert!(parse_condition("x: isize < y: i64", DUMMY_SP).is_err());
+    assert!(parse_condition("(x: u64 as usize) == 1usize", DUMMY_SP).is_ok());
+}
+
+#[test]
+fn pointer_sized_literals_fit_pointer_width() {
+    set_pointer_width(32);
+    let condition = "x: usize < 5000000000usize";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(22), BytePos(37))));
+    assert!(error.message.contains("Out of range value for usize type"));
+    assert!(parse_condition("x: usize <= 4294967295usize", DUMMY_SP).is_ok());
+    assert!(parse_condition("x: isize < 2147483648isize", DUMMY_SP).is_err());
+    assert!(parse_condition("x: isize > -2147483649isize", DUMMY_SP).is_err());
+    assert!(parse_condition("x: isize >= -2147483648isize", DUMMY_SP).is_ok());
+    assert_eq!(parse_condition("usize::MAX == 4294967295usize", DUMMY_SP).unwrap(),
+               parse_condition("4294967295usize == usize::MAX", DUMMY_SP).unwrap());
+    set_pointer_width(64);
+}
+
+#[test]
+fn parse_u128() {
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::LessThan,
+        left: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "x".to_string(),
+            var_type: "u128".to_string()
+        })),
+        right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
+            size: 128,
+            value: 7886392056514347008,
+            high: 5421010862
+        }))
+    });
+    assert_eq!(parse_condition("x: u128 < 100000000000000000000000000000u128", DUMMY_SP).unwrap(),
+               correct_result);
+    assert_eq!(determine_evaluation_type(&parse_condition("i128::MIN", DUMMY_SP).unwrap()), "i128");
+}
+
+#[test]
+fn parse_i128() {
+    assert_eq!(parse_condition("i128::MIN", DUMMY_SP).unwrap().to_string(),
+               "(-170141183460469231731687303715884105728 : i128)");
+    assert_eq!(parse_condition("-170141183460469231731687303715884105728i128", DUMMY_SP).unwrap(),
+               parse_condition("i128::MIN", DUMMY_SP).unwrap());
+    assert_eq!(parse_condition("170141183460469231731687303715884105727i128", DUMMY_SP).unwrap(),
+               parse_condition("i128::MAX", DUMMY_SP).unwrap());
+    assert_eq!(parse_condition("340282366920938463463374607431768211455u128", DUMMY_SP).unwrap(),
+               parse_condition("u128::MAX", DUMMY_SP).unwrap());
+    assert_eq!(parse_condition("-1i128", DUMMY_SP).unwrap().to_string(), "(-1 : i128)");
+}
+
+#[test]
+fn out_of_range_u128_is_reported_at_literal() {
+    let condition = "x: u128 < 340282366920938463463374607431768211456u128";
+    let error = parse_condition(condition, literal_span(condition)).unwrap_err();
+    assert_eq!(error.span, Some(mk_sp(BytePos(21), BytePos(64))));
+    assert!(error.message.contains("Out of range value for u128 type"));
+    assert!(parse_condition("170141183460469231731687303715884105728i128", DUMMY_SP)
+        .is_err());
+    assert!(parse_condition("-170141183460469231731687303715884105729i128", DUMMY_SP)
+        .is_err());
+}
+
+#[test]
+fn parse_cast() {
+    let var = |name: &str| Box::new(Expression::VariableMapping( VariableMappingData {
+        name: name.to_string(),
+        var_type: "u32".to_string()
+    }));
+    // as binds more tightly than *, and less tightly than unary operators
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::Multiplication,
+        left: Box::new(Expression::CastExpression( CastExpressionData {
+            e: var("x"),
+            var_type: "u64".to_string()
+        })),
+        right: Box::new(Expression::CastExpression( CastExpressionData {
+            e: Box::new(Expression::UnaryExpression( UnaryExpressionData {
+                op: UnaryOperator::BitwiseNot,
+                e: var("y")
+            })),
+            var_type: "u64".to_string()
+        }))
+    });
+    assert_eq!(parse_condition("x: u32 as u64 * !y: u32 as u64", DUMMY_SP).unwrap(),
+               correct_result);
+}
+
+#[test]
+fn parse_cast_to_bool() {
+    assert!(parse_condition("x: u32 as bool", DUMMY_SP).is_err());
+}
+
+#[test]
+fn parse_array_index_and_len() {
+    let array = Expression::VariableMapping( VariableMappingData {
+        name: "a".to_string(),
+        var_type: "[i8; 4]".to_string()
+    });
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::And,
+        left: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::LessThan,
+            left: Box::new(Expression::VariableMapping( VariableMappingData {
+                name: "i".to_string(),
+                var_type: "usize".to_string()
+            })),
+            // The length of an array is its constant length
+            right: Box::new(pointer_sized(Expression::UnsignedBitVector( UnsignedBitVectorData {
+                size: 64,
+                value: 4,
+                high: 0
+            }), "usize"))
+        })),
+        right: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::Equal,
+            left: Box::new(Expression::ArrayIndex( ArrayIndexData {
+                array: Box::new(array),
+                index: Box::new(Expression::VariableMapping( VariableMappingData {
+                    name: "i".to_string(),
+                    var_type: "usize".to_string()
+                }))
+            })),
+            right: Box::new(Expression::SignedBitVector( SignedBitVectorData {
+                size: 8,
+                value: 0,
+                high: 0
+            }))
+        }))
+    });
+    assert_eq!(parse_condition("(i: usize < len(a: [i8; 4])) AND \
+                                (a: &mut [i8; 4][i: usize] == 0i8)", DUMMY_SP).unwrap(),
+               correct_result);
+}
+
+#[test]
+fn parse_array_index_not_usize() {
+    assert!(parse_condition("a: [u32][i: u32] == 0u32", DUMMY_SP).is_err());
+}
+
+#[test]
+fn parse_field_paths() {
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::LessThan,
+        left: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "r.min.x".to_string(),
+            var_type: "i32".to_string()
+        })),
+        right: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "return.0".to_string(),
+            var_type: "i32".to_string()
+        }))
+    });
+    assert_eq!(parse_condition("r.min.x: i32 < return.0: i32", DUMMY_SP).unwrap(), correct_result);
+}
+
+#[test]
+fn parse_reference_types() {
+    // A reference stands for the value it refers to
+    let correct_result: Expression = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::Equal,
+        left: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "r".to_string(),
+            var_type: "u32".to_string()
+        })),
+        right: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "s".to_string(),
+            var_type: "u32".to_string()
+        }))
+    });
+    assert_eq!(parse_condition("r: &mut u32 == s: &u32", DUMMY_SP).unwrap(), correct_result);
+}
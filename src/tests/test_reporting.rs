@@ -7,3 +7,51 @@
 // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
+
+use reporting::Error;
+use syntax::codemap::{mk_sp, BytePos, Span};
+
+fn span(lo: u32, hi: u32) -> Span {
+    mk_sp(BytePos(lo), BytePos(hi))
+}
+
+fn unsupported_statement() -> Result<(), Error> {
+    rp_bail!("Unsupported statement");
+}
+
+fn unsupported_condition() -> Result<(), Error> {
+    rp_span_bail!(span(12, 14), "Unsupported condition");
+}
+
+fn invalid_type() -> Result<u32, Error> {
+    try!(Err("Invalid type".to_string()));
+    Ok(5u32)
+}
+
+#[test]
+fn bail_has_no_span() {
+    let error = unsupported_statement().unwrap_err();
+    assert_eq!(error.span, None);
+    assert_eq!(error.message, "Unsupported statement");
+}
+
+#[test]
+fn span_bail_keeps_known_span() {
+    let error = unsupported_condition().unwrap_err().or_span(span(0, 20));
+    assert_eq!(error.span, Some(span(12, 14)));
+    assert_eq!(error.message, "Unsupported condition");
+}
+
+#[test]
+fn or_span_keeps_innermost_span() {
+    let error = unsupported_statement().unwrap_err();
+    let error = error.or_span(span(5, 10)).or_span(span(0, 20));
+    assert_eq!(error.span, Some(span(5, 10)));
+}
+
+#[test]
+fn try_converts_string_errors() {
+    let error = invalid_type().unwrap_err();
+    assert_eq!(error.span, None);
+    assert_eq!(error.message, "Invalid type");
+}
@@ -0,0 +1,224 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use parser::parse_condition;
+use syntax::codemap::DUMMY_SP;
+use expression::*;
+use smt_output::{gen_script, logic, Solver, SOLVER_NAMES};
+
+// Compares scripts by their parentheses and atoms, so they may be laid out differently
+fn assert_same_script(script: &str, expected: &str) {
+    fn tokens(script: &str) -> Vec<String> {
+        script.replace("(", " ( ").replace(")", " ) ").split_whitespace()
+            .map(|token| token.to_string()).collect()
+    }
+    assert_eq!(tokens(script), tokens(expected));
+}
+
+#[test]
+fn gen_script_quantifier() {
+    let vc = parse_condition("forall(i: i8, (i: i8 < n: i8) => (i: i8 != -1i8))",
+                             DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic BV)\n\
+                          (declare-fun |n| () (_ BitVec 8))\n\
+                          (assert (not (forall ((|i| (_ BitVec 8))) \
+                          (=> (bvslt |i| |n|) (not (= |i| (_ bv255 8)))))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "BV"), correct_result);
+}
+
+#[test]
+fn gen_script_casts() {
+    let vc = parse_condition("((x: i8 as i32) < (y: u64 as i32)) OR (b: bool as u8 == 1u8)",
+                             DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 8))\n\
+                          (declare-fun |y| () (_ BitVec 64))\n\
+                          (declare-fun |b| () Bool)\n\
+                          (assert (not (or (bvslt ((_ sign_extend 24) |x|) \
+                          ((_ extract 31 0) |y|)) \
+                          (= (ite |b| (_ bv1 8) (_ bv0 8)) (_ bv1 8)))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn gen_script_mixed_shifts() {
+    let vc = parse_condition("(x: u8 << n: u32) == (y: u8 >> m: i64)", DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 8))\n\
+                          (declare-fun |n| () (_ BitVec 32))\n\
+                          (declare-fun |y| () (_ BitVec 8))\n\
+                          (declare-fun |m| () (_ BitVec 64))\n\
+                          (assert (not (= \
+                          ((_ extract 7 0) (bvshl ((_ zero_extend 24) |x|) |n|)) \
+                          ((_ extract 7 0) (bvlshr ((_ zero_extend 56) |y|) |m|)))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn gen_script_shifts_by_narrower_amounts() {
+    let vc = parse_condition("((x: i8 >> n: u16) == 0i8) AND ((y: u64 << m: i8) == 0u64)",
+                             DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 8))\n\
+                          (declare-fun |n| () (_ BitVec 16))\n\
+                          (declare-fun |y| () (_ BitVec 64))\n\
+                          (declare-fun |m| () (_ BitVec 8))\n\
+                          (assert (not (and \
+                          (= ((_ extract 7 0) (bvashr ((_ sign_extend 8) |x|) |n|)) (_ bv0 8)) \
+                          (= (bvshl |y| ((_ zero_extend 56) |m|)) (_ bv0 64)))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn gen_script_signed_remainder() {
+    let vc = parse_condition("((x: i8 % y: i8) == -1i8) AND ((a: u8 % b: u8) == 1u8)",
+                             DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 8))\n\
+                          (declare-fun |y| () (_ BitVec 8))\n\
+                          (declare-fun |a| () (_ BitVec 8))\n\
+                          (declare-fun |b| () (_ BitVec 8))\n\
+                          (assert (not (and (= (bvsrem |x| |y|) (_ bv255 8)) \
+                          (= (bvurem |a| |b|) (_ bv1 8)))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn gen_script_arrays() {
+    let vc = parse_condition("(i: usize < len(a: &[u32])) => (a: [u32][i: usize] <= 10u32)",
+                             DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_ABV)\n\
+                          (declare-fun |i| () (_ BitVec 64))\n\
+                          (declare-fun |a.len| () (_ BitVec 64))\n\
+                          (declare-fun |a| () (Array (_ BitVec 64) (_ BitVec 32)))\n\
+                          (assert (not (=> (bvult |i| |a.len|) \
+                          (bvule (select |a| |i|) (_ bv10 32)))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, logic(&vc)), correct_result);
+}
+
+#[test]
+fn gen_script_multiplication_checks() {
+    let l = Box::new(Expression::VariableMapping( VariableMappingData {
+        name: "x".to_string(),
+        var_type: "i8".to_string()
+    } ));
+    let r = Box::new(Expression::VariableMapping( VariableMappingData {
+        name: "y".to_string(),
+        var_type: "i8".to_string()
+    } ));
+    let vc = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::And,
+        left: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::SignedMultiplicationDoesNotOverflow,
+            left: l.clone(),
+            right: r.clone()
+        } )),
+        right: Box::new(Expression::BinaryExpression( BinaryExpressionData {
+            op: BinaryOperator::SignedMultiplicationDoesNotUnderflow,
+            left: l,
+            right: r
+        } ))
+    } );
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 8))\n\
+                          (declare-fun |y| () (_ BitVec 8))\n\
+                          (assert (not (and \
+                          (bvsle (bvmul ((_ sign_extend 8) |x|) ((_ sign_extend 8) |y|)) \
+                          ((_ sign_extend 8) (_ bv127 8))) \
+                          (bvsge (bvmul ((_ sign_extend 8) |x|) ((_ sign_extend 8) |y|)) \
+                          ((_ sign_extend 8) (_ bv128 8))))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn gen_script_unsigned_multiplication_check() {
+    let vc = Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::UnsignedMultiplicationDoesNotOverflow,
+        left: Box::new(Expression::VariableMapping( VariableMappingData {
+            name: "x".to_string(),
+            var_type: "u128".to_string()
+        } )),
+        right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
+            size: 128,
+            value: 3,
+            high: 0
+        } ))
+    } );
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 128))\n\
+                          (assert (not (= ((_ extract 255 128) \
+                          (bvmul ((_ zero_extend 128) |x|) ((_ zero_extend 128) (_ bv3 128)))) \
+                          (_ bv0 128))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn gen_script_wide_literals() {
+    let vc = parse_condition("(x: i128 == -2i128) AND (y: u128 == u128::MAX)", DUMMY_SP).unwrap();
+    let correct_result = "(set-option :produce-models true)\n\
+                          (set-logic QF_BV)\n\
+                          (declare-fun |x| () (_ BitVec 128))\n\
+                          (declare-fun |y| () (_ BitVec 128))\n\
+                          (assert (not (and \
+                          (= |x| (_ bv340282366920938463463374607431768211454 128)) \
+                          (= |y| (_ bv340282366920938463463374607431768211455 128)))))\n\
+                          (check-sat)\n\
+                          (get-model)\n";
+    assert_same_script(&gen_script(&vc, "QF_BV"), correct_result);
+}
+
+#[test]
+fn boolector_prints_models() {
+    let (program, args) = Solver::Boolector.command();
+    assert_eq!(program, "boolector");
+    assert!(args.contains(&"-m"));
+}
+
+#[test]
+fn solver_names() {
+    for name in SOLVER_NAMES {
+        assert_eq!(Solver::from_name(name).unwrap().name(), *name);
+    }
+    assert_eq!(Solver::from_name("CVC5"), Some(Solver::Cvc5));
+    assert_eq!(Solver::from_name("mathsat"), None);
+    assert_eq!(Solver::default(), Solver::Z3);
+}
+
+#[test]
+fn solvers_without_quantifiers() {
+    assert!(Solver::Z3.supports_quantifiers());
+    assert!(Solver::Bitwuzla.supports_quantifiers());
+    assert!(!Solver::Yices.supports_quantifiers());
+    assert!(!Solver::Boolector.supports_quantifiers());
+}
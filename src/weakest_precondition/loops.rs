@@ -0,0 +1,83 @@
+// The Rust-Proof Project is copyright 2016, Sami Sahli,
+// Michael Salter, Matthew Slocum, Vincent Schuster,
+// Bradley Rasmussen, Drew Gohman, and Matthew O'Brien.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Functions to find the loops in the MIR control-flow graph.
+
+use MirData;
+use rustc_data_structures::indexed_vec::Idx;
+
+/// Selects which verification condition `gen` is currently building, which determines what
+/// reaching a loop header or the end of the function contributes to the weakest precondition.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LoopCheck {
+    /// `P -> WP` from the start of the function.
+    Entry,
+    /// `I -> WP` from the loop header at the given index, where every path back to the header
+    /// must re-establish the invariant `I`.
+    Preservation(usize),
+    /// `I -> WP` from the loop header at the given index, where every path leaving the loop must
+    /// establish whatever follows it.
+    Exit(usize),
+}
+
+/// Finds the loop headers of a function, i.e. the targets of the back edges in its control-flow
+/// graph.
+///
+/// # Arguments:
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Return Value:
+/// * Returns the indices of the loop header blocks, in the order their loops appear in the source.
+///
+/// # Remarks:
+/// * A back edge is an edge to a block that is still on the depth-first search stack.
+/// * The order of the blocks in the MIR is not the order of the loops in the source, so headers
+///   are ordered by where their terminators start instead. An outer loop starts before the loops
+///   nested in it, and a loop starts before the loops that follow it.
+///
+pub fn find_loop_headers(data: &MirData) -> Vec<usize> {
+    let mut visited = vec![false; data.block_data.len()];
+    let mut on_stack = vec![false; data.block_data.len()];
+    let mut headers = Vec::new();
+
+    if !data.block_data.is_empty() {
+        find_back_edges(0, data, &mut visited, &mut on_stack, &mut headers);
+    }
+
+    headers.sort();
+    headers.dedup();
+    headers.sort_by_key(|&header| {
+        (data.block_data[header].terminator().source_info.span.lo, header)
+    });
+    headers
+}
+
+// Depth-first search that records the target of every back edge in `headers`
+fn find_back_edges(index: usize,
+                   data: &MirData,
+                   visited: &mut Vec<bool>,
+                   on_stack: &mut Vec<bool>,
+                   headers: &mut Vec<usize>) {
+    visited[index] = true;
+    on_stack[index] = true;
+
+    for successor in data.block_data[index].terminator().kind.successors().iter() {
+        let successor = successor.index();
+        if on_stack[successor] {
+            // Jumping back to a block on the stack closes a loop
+            headers.push(successor);
+        } else if !visited[successor] {
+            find_back_edges(successor, data, visited, on_stack, headers);
+        }
+    }
+
+    on_stack[index] = false;
+}
@@ -13,19 +13,20 @@
 extern crate rustc_const_math;
 
 use super::MirData;
-use std::process;
+use std::collections::{HashMap, HashSet};
 use expression::*;
 use rustc::mir::repr::*;
 use rustc::middle::const_val::ConstVal;
-use rustc_const_math::ConstInt;
+use rustc_const_math::{ConstInt, ConstIsize, ConstUsize, ConstMathErr};
 use rustc_data_structures::indexed_vec::Idx;
-use rustc::ty::{TypeVariants};
-
-use errors::{ColorConfig, Handler};
-use syntax::codemap::CodeMap;
-use std::rc::Rc;
+use rustc::ty::{TypeVariants, Ty, AdtKind};
+use reporting::Error;
+use syntax::codemap::Span;
 
 mod overflow;
+mod loops;
+
+pub use self::loops::{LoopCheck, find_loop_headers};
 
 /// Computes the weakest precondition for a given postcondition and a series of statements over one or more MIR basic blocks.
 ///
@@ -35,51 +36,116 @@ mod overflow;
 /// * `post_expr` - The current weakest precondition (originally the postcondition) as an Expression.
 ///
 /// # Return Value:
-/// * Returns the weakest precondition generated from the `BasicBlock` in the form of an Expression.
+/// * Returns the weakest precondition generated from the `BasicBlock` in the form of an Expression,
+///   or the error that stopped it.
 ///
 /// # Remarks:
 /// * This is the main generator for the weakest precondition, which evaluates the `BasicBlock`s recursively.
+/// * Successor blocks are entered through `gen_target`, which stops at loop headers.
+/// * Errors are given the span of the statement or terminator that caused them.
 ///
-pub fn gen(index: usize, data: &mut MirData, post_expr: &Option<Expression>, debug: bool) -> Option<Expression> {
-    let mut wp: Option<Expression>;
+pub fn gen(index: usize, data: &mut MirData, post_expr: &Expression, debug: bool)
+           -> Result<Expression, Error> {
+    let span = data.block_data[index].terminator().source_info.span;
+    gen_block(index, data, post_expr, debug).map_err(|e| e.or_span(span))
+}
+
+// Computes the weakest precondition of one basic block, see `gen`
+fn gen_block(index: usize, data: &mut MirData, post_expr: &Expression, debug: bool)
+             -> Result<Expression, Error> {
+    let mut wp: Expression;
 
     // Parse basic block terminator data
     let terminator = data.block_data[index].terminator.clone().unwrap().kind;
     match terminator {
+        // The compiler's checks (ie: that an index is within the bounds of its array, or that an
+        // operation does not overflow) must hold
+        // wp(assert(c == e); S) => (c == e) AND wp(S)
         // Assert{cond, expected, msg, target, cleanup}
-        TerminatorKind::Assert{target, ..}
-        | TerminatorKind::Goto{target} => {
+        TerminatorKind::Assert{cond, expected, msg, target, ..} => {
+            if debug {
+                println!("checking assertion\t{}", describe_assert(&msg));
+            }
+            let check = Expression::BinaryExpression( BinaryExpressionData{
+                op: BinaryOperator::Equal,
+                left: Box::new(try!(gen_expression(&cond, data))),
+                right: Box::new(Expression::BooleanLiteral(expected))
+            });
+            let span = data.block_data[index].terminator().source_info.span;
+            let check = add_obligation(check, &describe_assert(&msg), span, data);
+            wp = Expression::BinaryExpression( BinaryExpressionData{
+                op: BinaryOperator::And,
+                left: Box::new(check),
+                right: Box::new(try!(gen_target(target.index(), data, post_expr, debug)))
+            });
+        },
+        TerminatorKind::Goto{target} => {
             // Retrieve the weakest precondition from the following block
-            wp = gen(target.index(), data, post_expr, debug);
+            wp = try!(gen_target(target.index(), data, post_expr, debug));
         },
         TerminatorKind::Return => {
+            // Reaching the end of the function is not an obligation when checking that a loop
+            // invariant is preserved
+            if let LoopCheck::Preservation(_) = data.loop_check {
+                return Ok(Expression::BooleanLiteral(true));
+            }
             // Return the post condition to the preceeding block
-            return post_expr.clone();
+            return Ok(post_expr.clone());
         },
         // Call{func, args, destination, cleanup}
-        TerminatorKind::Call{func, ..} => {
+        TerminatorKind::Call{func, args, destination, ..} => {
             // Determine if this is the end of a panic. (assumed false branch of assertion, so
             // return a precondition of false [this path will never be taken])
             match func {
                 Operand::Constant (ref c) => {
                     let s = format!("{:?}", c.literal);
                     if s.contains("begin_panic") {
-                        return Some(Expression::BooleanLiteral(false));
+                        let span = data.block_data[index].terminator().source_info.span;
+                        return Ok(add_obligation(Expression::BooleanLiteral(false),
+                                                 "possible panic",
+                                                 span,
+                                                 data));
                     }
                 },
                 // Consume (ref l)
-                Operand::Consume (..) => unimplemented!(),
+                Operand::Consume (..) => {},
             };
-            // Due to the limited nature in which we handle Calls, we should never do anything
-            // other than return early or hit the unimplemented!() panic above.
-            unreachable!();
+            // Otherwise the callee's contract stands in for its body
+            match data.call_contracts.get(&index).cloned() {
+                Some(contract) => {
+                    wp = try!(gen_call(index, &contract, &args, &destination, data, post_expr,
+                                       debug));
+                },
+                // Without a contract the call is opaque, and nothing is known about its result
+                None => {
+                    wp = match destination {
+                        Some((lvalue, target)) => {
+                            let mut wp_target = try!(gen_target(target.index(), data, post_expr,
+                                                                debug));
+                            // Anything the callee can write through a `&mut` argument is unknown too
+                            for arg in args.iter() {
+                                if try!(gen_ty(arg, data)).starts_with("&mut ") {
+                                    let referent = try!(gen_referent(arg, data));
+                                    if let Expression::VariableMapping(place) = referent {
+                                        havoc(index, &place, &mut wp_target);
+                                    }
+                                }
+                            }
+                            havoc(index, &try!(gen_lvalue(lvalue, data)), &mut wp_target);
+                            wp_target
+                        },
+                        // The callee never returns
+                        None => Expression::BooleanLiteral(true),
+                    };
+                }
+            }
         },
         // Conditional statements
         // wp(if c x else y) => (c -> x) AND ((NOT c) -> y)
         TerminatorKind::If{cond, targets} => {
             // Generate weakest precondition for if and else clause
-            let wp_if = gen(targets.0.index(), data, post_expr, debug);
-            let wp_else = gen(targets.1.index(), data, post_expr, debug);
+            let wp_if = try!(gen_target(targets.0.index(), data, post_expr, debug));
+            let wp_else = try!(gen_target(targets.1.index(), data, post_expr, debug));
 
             // Generate the conditional expression
             let condition = match cond {
@@ -93,10 +159,10 @@ pub fn gen(index: usize, data: &mut MirData, post_expr: &Option<Expression>, deb
                                 _ => unreachable!(),
                             }
                         },
-                        _ => unimplemented!(),
+                        _ => rp_bail!("Unsupported condition {:?}", constant.literal),
                     }
                 },
-                Operand::Consume(c) => { Expression::VariableMapping(gen_lvalue(c, data)) },
+                Operand::Consume(c) => { Expression::VariableMapping(try!(gen_lvalue(c, data))) },
             };
             // Negate the conditional expression
             let not_condition = Expression::UnaryExpression(UnaryExpressionData {
@@ -104,31 +170,131 @@ pub fn gen(index: usize, data: &mut MirData, post_expr: &Option<Expression>, deb
                 e: Box::new(condition.clone())
             });
             // wp(If c x else y) => (c -> x) AND ((NOT c) -> y)
-            wp = Some(Expression::BinaryExpression(BinaryExpressionData {
+            wp = Expression::BinaryExpression(BinaryExpressionData {
                 op: BinaryOperator::And,
                 left: Box::new(Expression::BinaryExpression(BinaryExpressionData {
                     op: BinaryOperator::Implication,
                     left: Box::new(condition.clone()),
-                    right: Box::new(wp_if.unwrap())
+                    right: Box::new(wp_if)
                 })),
                 right: Box::new(Expression::BinaryExpression(BinaryExpressionData {
                     op: BinaryOperator::Implication,
                     left: Box::new(not_condition.clone()),
-                    right: Box::new(wp_else.unwrap())
+                    right: Box::new(wp_else)
                 }))
-            }));
+            });
         },
-        // Unimplemented TerminatorKinds
-        // DropAndReplace{location, value, target, unwind}
-        TerminatorKind::DropAndReplace{..} => unimplemented!(),
+        // Enum match statements, with one target per variant
+        // wp(switch d {V0 => x0, .., Vn => xn}) =>
+        //     ((d.discr == 0) -> x0) AND .. AND ((d.discr == n) -> xn)
+        TerminatorKind::Switch{discr, targets, ..} => {
+            let discr = Expression::VariableMapping(discriminant(&try!(gen_lvalue(discr, data))));
+
+            let mut switch_wp = Expression::BooleanLiteral(true);
+            for (variant, target) in targets.iter().enumerate() {
+                let wp_arm = try!(gen_target(target.index(), data, post_expr, debug));
+                switch_wp = Expression::BinaryExpression(BinaryExpressionData {
+                    op: BinaryOperator::And,
+                    left: Box::new(switch_wp),
+                    right: Box::new(Expression::BinaryExpression(BinaryExpressionData {
+                        op: BinaryOperator::Implication,
+                        left: Box::new(Expression::BinaryExpression(BinaryExpressionData {
+                            op: BinaryOperator::Equal,
+                            left: Box::new(discr.clone()),
+                            right: Box::new(Expression::UnsignedBitVector(UnsignedBitVectorData {
+                                size: 64,
+                                value: variant as u64,
+                                high: 0
+                            }))
+                        })),
+                        right: Box::new(wp_arm)
+                    }))
+                });
+            }
+            wp = switch_wp;
+        },
+        // Dropping a value has no effect on the values we verify
         // Drop{location, target, unwind}
-        TerminatorKind::Drop{..} => unimplemented!(),
-        TerminatorKind::Unreachable => unimplemented!(),
-        TerminatorKind::Resume => unimplemented!(),
-        // Switch{discr, adt_def, targets}
-        TerminatorKind::Switch{..} => unimplemented!(),
-        // SwitchInt{discr, switch_ty, values, targets}
-        TerminatorKind::SwitchInt{..} => unimplemented!(),
+        TerminatorKind::Drop{target, ..} => {
+            wp = try!(gen_target(target.index(), data, post_expr, debug));
+        },
+        // The old value is dropped and the new value assigned in its place
+        // wp(drop_replace l = v; S) => wp(l = v; S)
+        // DropAndReplace{location, value, target, unwind}
+        TerminatorKind::DropAndReplace{location, value, target, ..} => {
+            let wp_target = try!(gen_target(target.index(), data, post_expr, debug));
+            let assignment = Statement {
+                source_info: data.block_data[index].terminator().source_info,
+                kind: StatementKind::Assign(location, Rvalue::Use(value))
+            };
+            wp = try!(gen_stmt(wp_target, assignment, data, debug));
+        },
+        // Control never reaches an unreachable terminator, so any path to it must be infeasible
+        TerminatorKind::Unreachable => {
+            let span = data.block_data[index].terminator().source_info.span;
+            wp = add_obligation(Expression::BooleanLiteral(false),
+                                "reachable unreachable code",
+                                span,
+                                data);
+        },
+        // Unwinding only follows a panic, which must never happen, like the end of a panic above.
+        // This is not reached: cleanup edges are never followed, since every way to start
+        // unwinding (a failed `Assert`, a call to `begin_panic`) is already an obligation that must
+        // not hold, and the unwinding that follows it would only add the same obligation again
+        TerminatorKind::Resume => {
+            let span = data.block_data[index].terminator().source_info.span;
+            wp = add_obligation(Expression::BooleanLiteral(false), "possible panic", span, data);
+        },
+        // Integer match statements
+        // wp(switch d {v1 => x1, .., vn => xn, _ => y}) =>
+        //     ((d == v1) -> x1) AND .. AND ((d == vn) -> xn) AND (((d != v1) AND .. AND (d != vn)) -> y)
+        TerminatorKind::SwitchInt{discr, values, targets, ..} => {
+            let discr = Expression::VariableMapping(try!(gen_lvalue(discr, data)));
+
+            // The target of each value, and the conjunction of every value not being matched
+            let mut arms = Vec::new();
+            let mut otherwise = Expression::BooleanLiteral(true);
+            for (value, target) in values.iter().zip(targets.iter()) {
+                let value = try!(gen_const_val(value));
+                let wp_arm = try!(gen_target(target.index(), data, post_expr, debug));
+
+                arms.push(Expression::BinaryExpression(BinaryExpressionData {
+                    op: BinaryOperator::Implication,
+                    left: Box::new(Expression::BinaryExpression(BinaryExpressionData {
+                        op: BinaryOperator::Equal,
+                        left: Box::new(discr.clone()),
+                        right: Box::new(value.clone())
+                    })),
+                    right: Box::new(wp_arm)
+                }));
+                otherwise = Expression::BinaryExpression(BinaryExpressionData {
+                    op: BinaryOperator::And,
+                    left: Box::new(otherwise),
+                    right: Box::new(Expression::BinaryExpression(BinaryExpressionData {
+                        op: BinaryOperator::NotEqual,
+                        left: Box::new(discr.clone()),
+                        right: Box::new(value)
+                    }))
+                });
+            }
+
+            // The last target is taken when none of the values match
+            let otherwise_target = targets[values.len()].index();
+            let wp_otherwise = try!(gen_target(otherwise_target, data, post_expr, debug));
+            let mut switch_wp = Expression::BinaryExpression(BinaryExpressionData {
+                op: BinaryOperator::Implication,
+                left: Box::new(otherwise),
+                right: Box::new(wp_otherwise)
+            });
+            for arm in arms {
+                switch_wp = Expression::BinaryExpression(BinaryExpressionData {
+                    op: BinaryOperator::And,
+                    left: Box::new(arm),
+                    right: Box::new(switch_wp)
+                });
+            }
+            wp = switch_wp;
+        },
     }
 
     // Examine the statements in reverse order
@@ -142,16 +308,693 @@ pub fn gen(index: usize, data: &mut MirData, post_expr: &Option<Expression>, deb
 
     for stmt in stmts {
         // Modify the weakest precondition based on the statement
-        wp = gen_stmt(wp.unwrap(), stmt, data, debug);
+        let span = stmt.source_info.span;
+        wp = try!(gen_stmt(wp, stmt, data, debug).map_err(|e| e.or_span(span)));
     }
 
     // Prints the result to be returned to the proceeding block
     if debug {
-        println!("wp returned as\t{:?}\n", wp.clone().unwrap());
+        println!("wp returned as\t{:?}\n", wp);
     }
 
     // Return the weakest precondition to the preceeding block, or to control
-    wp
+    Ok(wp)
+}
+
+/// Something the weakest precondition requires to hold, which is checked and reported on its own.
+#[derive(Clone, Debug)]
+pub struct Obligation {
+    // What is checked, ie: "possible overflow" or "postcondition"
+    pub kind: String,
+    // Where it is checked
+    pub span: Span,
+}
+
+/// Tags a check that the weakest precondition requires as an obligation, so that it can be
+/// checked on its own.
+///
+/// # Arguments:
+/// * `check` - The boolean Expression that must hold.
+/// * `kind` - What is checked, ie: `"possible overflow"`.
+/// * `span` - Where it is checked.
+/// * `data` - Receives the obligation.
+///
+/// # Return Value:
+/// * Returns `@obligationN -> check`, where `@obligationN` selects the obligation.
+///
+/// # Remarks:
+/// * Checks of the same kind at the same location are the same obligation, however many paths
+///   reach them.
+/// * See `select_obligation` for how an obligation is checked.
+///
+pub fn add_obligation(check: Expression, kind: &str, span: Span, data: &mut MirData) -> Expression {
+    let index = match data.obligations.iter().position(|o| o.kind == kind && o.span == span) {
+        Some(index) => index,
+        None => {
+            data.obligations.push(Obligation{ kind: kind.to_string(), span: span });
+            data.obligations.len() - 1
+        },
+    };
+    Expression::BinaryExpression( BinaryExpressionData{
+        op: BinaryOperator::Implication,
+        left: Box::new(Expression::VariableMapping(obligation_selector(index))),
+        right: Box::new(check)
+    })
+}
+
+/// Returns the verification condition of one obligation.
+///
+/// # Arguments:
+/// * `vc` - A verification condition containing obligations made by `add_obligation`.
+/// * `index` - The index of the obligation in `MirData::obligations`.
+/// * `count` - The number of obligations in `MirData::obligations`.
+///
+/// # Return Value:
+/// * Returns `vc` with every other obligation taken as true, which is valid exactly when the
+///   obligation holds whenever it is reached.
+///
+pub fn select_obligation(vc: &Expression, index: usize, count: usize) -> Expression {
+    let mut selected = vc.clone();
+    for other in 0..count {
+        substitute_variable_with_expression(&mut selected,
+                                            &obligation_selector(other),
+                                            &Expression::BooleanLiteral(other == index));
+    }
+    selected
+}
+
+/// Returns the indices of the obligations that occur in a verification condition.
+///
+/// # Arguments:
+/// * `vc` - A verification condition containing obligations made by `add_obligation`.
+///
+pub fn find_obligations(vc: &Expression) -> Vec<usize> {
+    let mut variables = Vec::new();
+    collect_variables(vc, &mut variables);
+    let mut indices: Vec<usize> = variables.iter()
+                                           .filter_map(|v| {
+                                               if v.name.starts_with(OBLIGATION_PREFIX) {
+                                                   v.name[OBLIGATION_PREFIX.len()..].parse().ok()
+                                               } else {
+                                                   None
+                                               }
+                                           })
+                                           .collect();
+    indices.sort();
+    indices.dedup();
+    indices
+}
+
+// The prefix of the variables that select an obligation
+const OBLIGATION_PREFIX: &'static str = "@obligation";
+
+// Returns the variable that selects the obligation at the given index
+fn obligation_selector(index: usize) -> VariableMappingData {
+    VariableMappingData{
+        name: format!("{}{}", OBLIGATION_PREFIX, index),
+        var_type: "bool".to_string()
+    }
+}
+
+/// The pre- and postcondition of a function called from the function being analyzed.
+#[derive(Clone, Debug)]
+pub struct CallContract {
+    /// The path of the called function
+    pub name: String,
+    /// The names of the called function's formal arguments, in order
+    pub arg_names: Vec<String>,
+    pub pre: Expression,
+    pub post: Expression,
+}
+
+/// Describes what the compiler checks with an `Assert` terminator.
+///
+/// # Arguments:
+/// * `msg` - The message the program panics with if the assertion fails.
+///
+/// # Return Value:
+/// * Returns a description of the check, ie: `"index out of bounds"`.
+///
+pub fn describe_assert(msg: &AssertMessage) -> String {
+    match *msg {
+        AssertMessage::BoundsCheck{..} => "index out of bounds".to_string(),
+        AssertMessage::Math(ref err) => {
+            match *err {
+                ConstMathErr::Overflow(_) => "possible overflow".to_string(),
+                ConstMathErr::DivisionByZero => "possible division by zero".to_string(),
+                ConstMathErr::RemainderByZero => "possible remainder by zero".to_string(),
+                _ => format!("possible {}", err.description()),
+            }
+        },
+    }
+}
+
+// Returns the negation of a boolean Expression
+fn negate(e: Expression) -> Expression {
+    Expression::UnaryExpression( UnaryExpressionData {
+        op: UnaryOperator::Not,
+        e: Box::new(e)
+    } )
+}
+
+/// Computes the weakest precondition of a call to a function with a contract.
+///
+/// # Arguments:
+/// * `index` - The index of the `BasicBlock` the call terminates.
+/// * `contract` - The pre- and postcondition of the called function.
+/// * `args` - The actual arguments of the call.
+/// * `destination` - Where the returned value is stored, and the block that follows the call.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+/// * `post_expr` - The postcondition of the function as an Expression.
+///
+/// # Return Value:
+/// * Returns the weakest precondition of the call, or the error that stopped it.
+///
+/// # Remarks:
+/// * wp(d = f(a); S) => pre[a/x] AND (post[a/x, d'/return] -> wp(S)[d'/d]), where `x` are the
+///   formal arguments of `f` and `d'` is a fresh variable standing for the returned value.
+/// * See `havoc` for how the returned value is replaced with a fresh variable.
+/// * A reference argument stands for the value it refers to. The value a `&mut` argument refers
+///   to is replaced with a fresh variable too: in the postcondition, `x` is its value on return
+///   and `old(x)` its value at the call.
+///
+fn gen_call(index: usize,
+            contract: &CallContract,
+            args: &Vec<Operand>,
+            destination: &Option<(Lvalue, BasicBlock)>,
+            data: &mut MirData,
+            post_expr: &Expression,
+            debug: bool)
+            -> Result<Expression, Error> {
+    let span = data.block_data[index].terminator().source_info.span;
+    let mut pre = add_obligation(contract.pre.clone(),
+                                 &format!("precondition of {}", contract.name),
+                                 span,
+                                 data);
+    let mut post = contract.post.clone();
+
+    if contract.arg_names.len() != args.len() {
+        rp_bail!("fn {}(..) expects {} argument(s) but {} were provided.",
+                 contract.name, contract.arg_names.len(), args.len());
+    }
+
+    // Rename the formal arguments first, so an actual argument that shares its name with
+    // another formal argument is not substituted twice
+    let mut placeholders = Vec::new();
+    let mut written = Vec::new();
+    for (position, (name, arg)) in contract.arg_names.iter().zip(args.iter()).enumerate() {
+        let actual = try!(gen_referent(arg, data));
+        let formal = VariableMappingData{
+            name: name.clone(),
+            var_type: determine_evaluation_type(&actual)
+        };
+        let placeholder = VariableMappingData{
+            name: format!("@arg{}", position),
+            var_type: formal.var_type.clone()
+        };
+        // The value written through a `&mut` argument is only known through the postcondition
+        if try!(gen_ty(arg, data)).starts_with("&mut ") {
+            let written_placeholder = VariableMappingData{
+                name: format!("@arg{}@post", position),
+                var_type: formal.var_type.clone()
+            };
+            substitute_variable_fields(&mut post, &formal.name, &written_placeholder.name);
+            substitute_variable_with_expression(&mut post,
+                                                &formal,
+                                                &Expression::VariableMapping(written_placeholder.clone()));
+            match actual {
+                Expression::VariableMapping(ref place) => {
+                    written.push((written_placeholder, place.clone()));
+                },
+                _ => rp_bail!("Unsupported mutable borrow passed to {}", contract.name),
+            }
+        }
+        let entry = VariableMappingData{
+            name: formal.name.clone() + OLD_SUFFIX,
+            var_type: formal.var_type.clone()
+        };
+        for condition in vec![&mut pre, &mut post] {
+            for formal in vec![&formal, &entry] {
+                substitute_variable_fields(condition, &formal.name, &placeholder.name);
+                substitute_variable_with_expression(condition,
+                                                    formal,
+                                                    &Expression::VariableMapping(placeholder.clone()));
+            }
+        }
+        placeholders.push((placeholder, actual));
+    }
+
+    // Then bind the actual arguments
+    for &(ref placeholder, ref actual) in placeholders.iter() {
+        for condition in vec![&mut pre, &mut post] {
+            if let Expression::VariableMapping(ref v) = *actual {
+                substitute_variable_fields(condition, &placeholder.name, &v.name);
+            }
+            substitute_variable_with_expression(condition, placeholder, actual);
+        }
+    }
+
+    match *destination {
+        Some((ref lvalue, ref target)) => {
+            let var = try!(gen_lvalue(lvalue.clone(), data));
+
+            // The returned value, and the values written through `&mut` arguments, are only
+            // known through the postcondition
+            let mut wp_target = try!(gen_target(target.index(), data, post_expr, debug));
+            for &(ref written_placeholder, ref place) in written.iter() {
+                let value = havoc(index, place, &mut wp_target);
+                substitute_variable_fields(&mut post, &written_placeholder.name, &value.name);
+                substitute_variable_with_expression(&mut post,
+                                                    written_placeholder,
+                                                    &Expression::VariableMapping(value));
+            }
+            let result = havoc(index, &var, &mut wp_target);
+            let ret = VariableMappingData{
+                name: "return".to_string(),
+                var_type: var.var_type.clone()
+            };
+            substitute_variable_fields(&mut post, &ret.name, &result.name);
+            substitute_variable_with_expression(&mut post,
+                                                &ret,
+                                                &Expression::VariableMapping(result));
+
+            Ok(Expression::BinaryExpression(BinaryExpressionData {
+                op: BinaryOperator::And,
+                left: Box::new(pre),
+                right: Box::new(Expression::BinaryExpression(BinaryExpressionData {
+                    op: BinaryOperator::Implication,
+                    left: Box::new(post),
+                    right: Box::new(wp_target)
+                }))
+            }))
+        },
+        // The callee never returns, so only its precondition matters
+        None => Ok(pre),
+    }
+}
+
+// Returns the value an argument of a call stands for: the value it refers to if it is a reference,
+// or the argument itself otherwise
+fn gen_referent(operand: &Operand, data: &mut MirData) -> Result<Expression, Error> {
+    if let Operand::Consume(ref lvalue) = *operand {
+        if try!(gen_lvalue(lvalue.clone(), data)).var_type.starts_with('&') {
+            return gen_expression(&Operand::Consume(lvalue.clone().deref()), data);
+        }
+    }
+    gen_expression(operand, data)
+}
+
+/// Replaces the value stored by a call with a fresh, unconstrained variable in the weakest
+/// precondition of the block that follows the call.
+///
+/// # Arguments:
+/// * `index` - The index of the `BasicBlock` the call terminates.
+/// * `var` - The variable the call stores its returned value in.
+/// * `wp` - The weakest precondition of the block that follows the call.
+///
+/// # Return Value:
+/// * Returns the fresh variable.
+///
+/// # Remarks:
+/// * The fresh variable is named after the block of the call, which is visited at most once on
+///   any path through the function. Being free in the verification condition, it may take any
+///   value of its type.
+///
+fn havoc(index: usize, var: &VariableMappingData, wp: &mut Expression) -> VariableMappingData {
+    let result = VariableMappingData{
+        name: format!("{}@bb{}", var.name, index),
+        var_type: var.var_type.clone()
+    };
+    substitute_variable_fields(wp, &var.name, &result.name);
+    substitute_variable_with_expression(wp, var, &Expression::VariableMapping(result.clone()));
+    result
+}
+
+/// Computes the weakest precondition on entry to a successor block.
+///
+/// # Arguments:
+/// * `index` - The index of the successor `BasicBlock` within MIR.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+/// * `post_expr` - The postcondition of the function as an Expression.
+///
+/// # Return Value:
+/// * Returns the weakest precondition generated from the successor block, or the invariant of
+///   the loop if the successor is a loop header, or the error that stopped it.
+///
+/// # Remarks:
+/// * Loop headers are never entered from here; their invariant must hold each time control
+///   reaches them, and the loop body is checked separately starting from the header.
+///
+pub fn gen_target(index: usize, data: &mut MirData, post_expr: &Expression, debug: bool)
+                  -> Result<Expression, Error> {
+    if let Some(invariant) = data.loop_invariants.get(&index).cloned() {
+        // Going around the loop again is checked by the preservation condition
+        if data.loop_check == LoopCheck::Exit(index) {
+            return Ok(Expression::BooleanLiteral(true));
+        }
+        let span = data.block_data[index].terminator().source_info.span;
+        return Ok(add_obligation(invariant, "loop invariant", span, data));
+    }
+    gen(index, data, post_expr, debug)
+}
+
+/// Replaces each check of an enum variant in a user-supplied condition with a comparison of the
+/// enum value's discriminant.
+///
+/// # Arguments:
+/// * `condition` - The condition whose variant checks are resolved.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Remarks:
+/// * Only enums that are the type of an argument, variable, temp, or the return value are known.
+///
+pub fn resolve_variants(condition: &mut Expression, data: &MirData) -> Result<(), Error> {
+    try!(resolve_variant_checks(condition, &data.enum_variants));
+    Ok(())
+}
+
+/// Replaces each `old(..)` expression in a user-supplied condition with an expression over
+/// snapshots of the entry values.
+///
+/// # Arguments:
+/// * `condition` - The condition whose `old(..)` expressions are resolved.
+/// * `suffix` - Names the snapshots, see `expression::resolve_old_expressions`.
+///
+pub fn resolve_old(condition: &mut Expression, suffix: &str) -> Result<(), Error> {
+    try!(resolve_old_expressions(condition, suffix));
+    Ok(())
+}
+
+/// Replaces the names of struct fields in a user-supplied condition with their positions, which
+/// is how fields are named in the weakest precondition (ie: `p.x` becomes `p.0`).
+///
+/// # Arguments:
+/// * `condition` - The condition whose fields are resolved.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Remarks:
+/// * Arguments, local variables (by their names in the source) and the return value can have
+///   their fields named.
+/// * Variant checks must already be resolved, so that fields holding enums can be checked too.
+///
+pub fn resolve_fields(condition: &mut Expression, data: &MirData) -> Result<(), Error> {
+    let mut var_types = HashMap::new();
+    for arg in data.arg_data.iter() {
+        var_types.insert(arg.debug_name.as_str().to_string(), referent_type(&type_name(arg.ty)));
+    }
+    for var in data.var_data.iter() {
+        // If several locals share a name, the first one declared is used
+        var_types.entry(var.name.as_str().to_string())
+                 .or_insert(referent_type(&type_name(var.ty)));
+    }
+    var_types.insert("return".to_string(), referent_type(&data.func_return_type));
+    resolve_field_names(condition, &var_types, &data.struct_fields)
+}
+
+/// Replaces the names of struct fields in a condition with their positions, given the types of
+/// the variables in the condition.
+///
+/// # Arguments:
+/// * `condition` - The condition whose fields are resolved.
+/// * `var_types` - The type of each variable that can appear in the condition, by name.
+/// * `struct_fields` - The name and type of each field of each struct type, by type name.
+///
+/// # Remarks:
+/// * A field that is named after the end of a path through structs (ie: the `Some` in
+///   `p.opt.Some.0`) is left as it is.
+///
+pub fn resolve_field_names(condition: &mut Expression,
+                           var_types: &HashMap<String, String>,
+                           struct_fields: &HashMap<String, Vec<(String, String)>>)
+                           -> Result<(), Error> {
+    let mut variables = Vec::new();
+    collect_variables(condition, &mut variables);
+    for v in variables {
+        let mut segments = v.name.split('.');
+        let mut name = segments.next().unwrap().to_string();
+        let mut var_type = var_types.get(&name).cloned();
+        for segment in segments {
+            let fields = var_type.as_ref().and_then(|t| struct_fields.get(t));
+            match fields {
+                Some(fields) => {
+                    match fields.iter().position(|&(ref field, _)| field == segment) {
+                        Some(index) => {
+                            name = format!("{}.{}", name, index);
+                            var_type = Some(fields[index].1.clone());
+                        },
+                        None => {
+                            rp_bail!("No field `{}` on type `{}` in {}",
+                                     segment, var_type.unwrap(), v.name);
+                        },
+                    }
+                },
+                None => {
+                    name = format!("{}.{}", name, segment);
+                    var_type = None;
+                },
+            }
+        }
+        if let Some(ref field_type) = var_type {
+            if v.name.contains('.') && *field_type != v.var_type {
+                rp_bail!("Field {} has type {}, not {}", v.name, field_type, v.var_type);
+            }
+        }
+        if name != v.name {
+            let field = VariableMappingData { name: name, var_type: v.var_type.clone() };
+            substitute_variable_with_expression(condition, &v, &Expression::VariableMapping(field));
+        }
+    }
+    Ok(())
+}
+
+/// Records the names and types of the fields of a struct or tuple type, so conditions can refer to
+/// its fields by name.
+///
+/// # Arguments:
+/// * `ty` - The type of an argument, variable, temp, or the return value.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Remarks:
+/// * Types other than structs and tuples, and references to them, are ignored.
+/// * The types of the fields are recorded too, so that fields of fields can be named.
+/// * The fields of a tuple are named by their position (ie: `return.0`).
+///
+pub fn record_struct_fields(ty: Ty, data: &mut MirData) {
+    // A reference is named by the value it refers to
+    if let TypeVariants::TyRef(_, ref referent) = ty.sty {
+        return record_struct_fields(referent.ty, data);
+    }
+    if let TypeVariants::TyTuple(elements) = ty.sty {
+        let name = type_name(ty);
+        if data.struct_fields.contains_key(&name) {
+            return;
+        }
+        let fields = elements.iter()
+                             .enumerate()
+                             .map(|(index, &element)| (index.to_string(), type_name(element)))
+                             .collect();
+        data.struct_fields.insert(name, fields);
+        for &element in elements.iter() {
+            record_struct_fields(element, data);
+            record_enum_variants(element, data);
+        }
+    }
+    if let TypeVariants::TyStruct(adt_def, _) = ty.sty {
+        let name = type_name(ty);
+        if data.struct_fields.contains_key(&name) {
+            return;
+        }
+        let fields = adt_def.struct_variant().fields.iter()
+                            .map(|field| {
+                                (field.name.as_str().to_string(), type_name(field.unsubst_ty()))
+                            })
+                            .collect();
+        data.struct_fields.insert(name, fields);
+        for field in adt_def.struct_variant().fields.iter() {
+            record_struct_fields(field.unsubst_ty(), data);
+            record_enum_variants(field.unsubst_ty(), data);
+        }
+    }
+}
+
+/// Records the variant names of an enum type, so conditions can refer to its variants by name.
+///
+/// # Arguments:
+/// * `ty` - The type of an argument, variable, temp, or the return value.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Remarks:
+/// * Types other than enums, and references to enums, are ignored.
+///
+pub fn record_enum_variants(ty: Ty, data: &mut MirData) {
+    match ty.sty {
+        TypeVariants::TyEnum(adt_def, _) => {
+            let variants = adt_def.variants.iter()
+                                  .map(|variant| variant.name.as_str().to_string())
+                                  .collect();
+            data.enum_variants.insert(type_name(ty), variants);
+        },
+        // A reference is named by the value it refers to
+        TypeVariants::TyRef(_, ref referent) => record_enum_variants(referent.ty, data),
+        _ => {},
+    }
+}
+
+/// Collects the variant names of every enum type that appears in a list of types, including in
+/// their fields, so a condition written against them can refer to variants by name.
+///
+/// # Arguments:
+/// * `types` - The types of a function's arguments and return value.
+///
+/// # Return Value:
+/// * Returns the variant names of each enum type, by type name, as in `MirData::enum_variants`.
+///
+/// # Remarks:
+/// * Used for the contracts of called functions, whose enums need not appear in the caller.
+///
+pub fn signature_enum_variants(types: &[Ty]) -> HashMap<String, Vec<String>> {
+    let mut enum_variants = HashMap::new();
+    let mut visited = HashSet::new();
+    for &ty in types {
+        collect_enum_variants(ty, &mut enum_variants, &mut visited);
+    }
+    enum_variants
+}
+
+// Records the variant names of the enums in a type, looking through references, tuples and struct
+// fields. `visited` holds the structs already looked through, so recursive types end
+fn collect_enum_variants(ty: Ty,
+                         enum_variants: &mut HashMap<String, Vec<String>>,
+                         visited: &mut HashSet<String>) {
+    match ty.sty {
+        TypeVariants::TyEnum(adt_def, _) => {
+            let variants = adt_def.variants.iter()
+                                  .map(|variant| variant.name.as_str().to_string())
+                                  .collect();
+            enum_variants.insert(type_name(ty), variants);
+        },
+        TypeVariants::TyRef(_, ref referent) => {
+            collect_enum_variants(referent.ty, enum_variants, visited);
+        },
+        TypeVariants::TyTuple(elements) => {
+            for &element in elements.iter() {
+                collect_enum_variants(element, enum_variants, visited);
+            }
+        },
+        TypeVariants::TyStruct(adt_def, _) => {
+            if visited.insert(type_name(ty)) {
+                for field in adt_def.struct_variant().fields.iter() {
+                    collect_enum_variants(field.unsubst_ty(), enum_variants, visited);
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Records the place each reference in a function borrows, so that reading or writing through
+/// the reference reads or writes that place.
+///
+/// # Arguments:
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Return Value:
+/// * Returns the error that stopped it, if any, at the statement that caused it.
+///
+/// # Remarks:
+/// * A reference that is copied from another reference borrows what the other one refers to.
+/// * References that are not made in the function (ie: arguments) are not recorded. They stand
+///   for the value they refer to.
+///
+pub fn record_references(data: &mut MirData) -> Result<(), Error> {
+    for index in 0..data.block_data.len() {
+        let block = data.block_data[index];
+        for stmt in block.statements.iter() {
+            let span = stmt.source_info.span;
+            let StatementKind::Assign(ref lvalue, ref rvalue) = stmt.kind;
+            let place = match *rvalue {
+                // Ref(ref region, ref borrow_kind, ref lvalue)
+                Rvalue::Ref(_, _, ref place) => place.clone(),
+                // Places that are not supported are reported by `gen_stmt`
+                Rvalue::Use(Operand::Consume(ref source)) => {
+                    match gen_lvalue(lvalue.clone(), data) {
+                        Ok(ref var) if var.var_type.starts_with('&') => source.clone().deref(),
+                        _ => continue,
+                    }
+                },
+                _ => continue,
+            };
+            let var = try!(gen_lvalue(lvalue.clone(), data).map_err(|e| e.or_span(span)));
+            match data.references.get(&var.name) {
+                Some(borrowed) if *borrowed != place => {
+                    let message = format!("Unsupported reassignment of the reference {:?}", lvalue);
+                    return Err(Error::at(span, message));
+                },
+                _ => {},
+            }
+            data.references.insert(var.name, place);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of a type as it is written in conditions, without any module paths
+/// (ie: `std::option::Option<u32>` becomes `Option<u32>`).
+///
+/// # Arguments:
+/// * `ty` - The type being named.
+///
+/// # Return Value:
+/// * Returns the name of the type as a `String`.
+///
+pub fn type_name(ty: Ty) -> String {
+    let mut name = String::new();
+    for c in ty.to_string().chars() {
+        name.push(c);
+        // Drop each path segment as soon as it is complete
+        if name.ends_with("::") {
+            let len = name.len() - 2;
+            name.truncate(len);
+            while name.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                name.pop();
+            }
+        }
+    }
+    name
+}
+
+/// Replaces the source names of local variables in a user-supplied condition with the names used
+/// for them in the weakest precondition (ie: `i: u32` becomes `var0: u32`).
+///
+/// # Arguments:
+/// * `condition` - The condition whose local variables are renamed.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Remarks:
+/// * If several locals share a name, the first one declared is used.
+/// * Variant checks must already be resolved, so that the discriminants are renamed too.
+///
+pub fn resolve_local_names(condition: &mut Expression, data: &MirData) {
+    for index in 0..data.var_data.len() {
+        let source = VariableMappingData{
+            name: data.var_data[index].name.as_str().to_string(),
+            var_type: type_name(data.var_data[index].ty)
+        };
+        let local = VariableMappingData{
+            name: "var".to_string() + index.to_string().as_str(),
+            var_type: source.var_type.clone()
+        };
+        substitute_variable_fields(condition, &source.name, &local.name);
+        substitute_variable_with_expression(condition, &source, &Expression::VariableMapping(local));
+    }
 }
 
 /// Returns the type of an operand as a `String` (ie: `"i32"`, `"bool"`, etc.)
@@ -162,92 +1005,39 @@ pub fn gen(index: usize, data: &mut MirData, post_expr: &Option<Expression>, deb
 ///            the MIR pass.
 ///
 /// # Remarks:
+/// * The type of a field or dereference is the type of the place, see `gen_lvalue`.
 ///
-fn gen_ty(operand: &Operand, data: &mut MirData) -> String {
+fn gen_ty(operand: &Operand, data: &mut MirData) -> Result<String, Error> {
     match operand.clone() {
-        Operand::Constant(ref constant) => constant.ty.to_string(),
+        Operand::Constant(ref constant) => Ok(type_name(constant.ty)),
         Operand::Consume(ref lvalue) => {
             match *lvalue {
                 // Function argument
                 Lvalue::Arg(ref arg) => {
-                    data.arg_data[arg.index()].ty.to_string()
+                    Ok(type_name(data.arg_data[arg.index()].ty))
                 },
                 // Temporary variable
                 Lvalue::Temp(ref temp) => {
-                    data.temp_data[temp.index()].ty.to_string()
+                    Ok(type_name(data.temp_data[temp.index()].ty))
                 },
                 // Local variable
                 Lvalue::Var(ref var) => {
-                    data.var_data[var.index()].ty.to_string()
+                    Ok(type_name(data.var_data[var.index()].ty))
                 },
-                _ => unimplemented!(),
+                _ => Ok(try!(gen_lvalue(lvalue.clone(), data)).var_type),
             }
         }
     }
 }
 
-/// Generates a version of wp "And"ed together with a conditional expression that mimics a check
-/// to ensure division by 0 does not occur.
-///
-/// # Arguments:
-/// * `wp` - The current weakest precondition that the "div by 0" is to be "And"ed to
-/// * `exp` - The expression to check to make sure it is not divided by 0
-///
-/// # Return Value:
-/// * Returns the modified weakest precondition with "div by 0" Expression "And"ed
-///
-/// # Remarks:
-/// * Currently supported `ConstInt`: `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`
-///
-fn add_zero_check(wp: &Expression, exp: &Expression) -> Expression {
-
-    if determine_evaluation_type(exp).starts_with('i') {
-        Expression::BinaryExpression( BinaryExpressionData{
-            // And weakest precondtion and ovflow check
-            op: BinaryOperator::And,
-            left: Box::new(wp.clone()),
-            right: Box::new(Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::NotEqual,
-                // The expresison to be checked
-                left: Box::new(exp.clone()),
-                // Need to set appropriate type with value of 0
-                right: Box::new(Expression::SignedBitVector( SignedBitVectorData {
-                    // The bit-vector size of the given type
-                    size: match determine_evaluation_type(exp).as_str() {
-                        "i8" => 8,
-                        "i16" => 16,
-                        "i32" => 32,
-                        "i64" => 64,
-                        _ => rp_error!("Unimplemented checkeddAdd right-hand operand type"),
-                    },
-                    value: 0
-                }))
-            }))
-        })
-    } else {
-        Expression::BinaryExpression( BinaryExpressionData{
-            // And weakest precondtion and ovflow check
-            op: BinaryOperator::And,
-            left: Box::new(wp.clone()),
-            right: Box::new(Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::NotEqual,
-                // The expresison to be checked
-                left: Box::new(exp.clone()),
-                // Need to set appropriate type with value of 0
-                right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
-                    // The bit-vector size of the given type
-                    size: match determine_evaluation_type(exp).as_str() {
-                        "u8" => 8,
-                        "u16" => 16,
-                        "u32" => 32,
-                        "u64" => 64,
-                        _ => rp_error!("Unimplemented checkeddAdd right-hand operand type"),
-                    },
-                    value: 0
-                }))
-            }))
-        })
-    }
+// And-s a check to the weakest precondition, as an obligation of the given kind
+fn add_check(wp: &Expression, check: Expression, kind: &str, span: Span, data: &mut MirData)
+             -> Expression {
+    Expression::BinaryExpression( BinaryExpressionData{
+        op: BinaryOperator::And,
+        left: Box::new(wp.clone()),
+        right: Box::new(add_obligation(check, kind, span, data))
+    })
 }
 
 
@@ -260,16 +1050,18 @@ fn add_zero_check(wp: &Expression, exp: &Expression) -> Expression {
 ///            the MIR pass.
 ///
 /// # Return Value:
-/// * Returns the modified weakest precondition with underflow check
+/// * Returns the modified weakest precondition with underflow check, or the error that stopped it
 ///
 /// # Remarks:
+/// * Errors are given the span of the statement by `gen`.
 ///
 fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool)
-            -> Option<Expression>  {
+            -> Result<Expression, Error> {
     // Prints the current statement being processed.
     if debug {
         println!("processing statement\t{:?}\ninto expression\t\t{:?}", stmt, wp);
     }
+    let span = stmt.source_info.span;
 
     let lvalue: Option<Lvalue>;
     let rvalue: Option<Rvalue>;
@@ -282,51 +1074,73 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
         },
         //_ => return Some(wp)
     }
-    // The variable or temp on the left-hand side of the assignment
-    let mut var = gen_lvalue(lvalue.unwrap(), data);
+    // The variable or temp on the left-hand side of the assignment. Assigning an element of an
+    // array assigns the whole array, with the element replaced, so the element's array and index
+    // are kept aside and `var` stands for the element until then
+    let mut store = None;
+    let mut var = match try!(borrowed_place(&lvalue.unwrap(), data)) {
+        Lvalue::Projection(ref pro) if is_index(&pro.elem) => {
+            let array = try!(gen_lvalue(pro.base.clone(), data));
+            let index = match pro.elem {
+                ProjectionElem::Index(ref index) => try!(gen_expression(index, data)),
+                _ => unreachable!(),
+            };
+            let element = VariableMappingData {
+                name: array.name.clone() + "[]",
+                var_type: element_type(&array.var_type).unwrap()
+            };
+            store = Some((array, index));
+            element
+        },
+        lvalue => try!(gen_lvalue(lvalue, data)),
+    };
 
     // The expression on the right-hand side of the assignment
     let mut expression = Vec::new();
     match rvalue.clone().unwrap() {
         Rvalue::CheckedBinaryOp(ref binop, ref loperand, ref roperand) => {
-            let lvalue: Expression = gen_expression(loperand, data);
-            let rvalue: Expression = gen_expression(roperand, data);
+            let lvalue: Expression = try!(gen_expression(loperand, data));
+            let rvalue: Expression = try!(gen_expression(roperand, data));
+            // The result is the first field of a (value, overflowed) tuple. The `Assert` that
+            // follows the operation checks the second field
+            let flag = VariableMappingData {
+                name: var.name.clone() + ".1",
+                var_type: "bool".to_string()
+            };
+            var = VariableMappingData {
+                name: var.name + ".0",
+                var_type: determine_evaluation_type(&lvalue)
+            };
+            let mut overflowed = Expression::BooleanLiteral(false);
             let op: BinaryOperator = match *binop {
                 BinOp::Add => {
-                    // Add the overflow expression checks
-                    wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
+                    // The overflow and underflow expression checks
+                    overflowed = negate(try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue)));
                     BinaryOperator::Addition
                 },
                 BinOp::Sub => {
-                    // Add the overflow and underflow expression checks
-                    wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
+                    // The overflow and underflow expression checks
+                    overflowed = negate(try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue)));
                     BinaryOperator::Subtraction
                 },
                 BinOp::Mul => {
-                    // Add the overflow and underflow expression checks
-                    wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
+                    // The overflow and underflow expression checks
+                    overflowed = negate(try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue)));
                     BinaryOperator::Multiplication
                 },
-                BinOp::Div => {
-                    // Add the overflow and underflow expression checks, if operands are signed
-                    if determine_evaluation_type(&rvalue).starts_with('i') {
-                        wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
-                    }
-                    // Add the division by 0 expression check
-                    wp = add_zero_check(&wp, &rvalue);
-                    BinaryOperator::Division
-                },
-                BinOp::Rem => {
-                    // Add the division by 0 expression check
-                    wp = add_zero_check(&wp, &rvalue);
-                    BinaryOperator::Modulo
-                },
-                BinOp::Shl => { BinaryOperator::BitwiseLeftShift },
-                BinOp::Shr => { BinaryOperator::BitwiseRightShift },
-                _ => { rp_error!("Unsupported checked binary operation!"); }
+                BinOp::Shl => {
+                    // The shift amount check
+                    overflowed = negate(try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue)));
+                    BinaryOperator::BitwiseLeftShift
+                },
+                BinOp::Shr => {
+                    // The shift amount check
+                    overflowed = negate(try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue)));
+                    BinaryOperator::BitwiseRightShift
+                },
+                _ => { rp_bail!("Unsupported checked binary operation!"); }
             };
-
-            var.name = var.name + ".0";
+            substitute_variable_with_expression(&mut wp, &flag, &overflowed);
 
             // Add the new BinaryExpressionData to the expression vector
             expression.push(Expression::BinaryExpression( BinaryExpressionData {
@@ -337,43 +1151,46 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
         },
 
         Rvalue::BinaryOp(ref binop, ref lval, ref rval) => {
-            let lvalue: Expression = gen_expression(lval, data);
-            let rvalue: Expression = gen_expression(rval, data);
+            let lvalue: Expression = try!(gen_expression(lval, data));
+            let rvalue: Expression = try!(gen_expression(rval, data));
             let op: BinaryOperator = match *binop {
                 BinOp::Add => {
                     // Add the overflow expression check
-                    wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
+                    let check = try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue));
+                    wp = add_check(&wp, check, "possible overflow", span, data);
                     BinaryOperator::Addition
                 },
                 BinOp::Sub => {
                     // Add the overflow and underflow expression checks
-                    wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
+                    let check = try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue));
+                    wp = add_check(&wp, check, "possible overflow", span, data);
                     BinaryOperator::Subtraction
                 },
                 BinOp::Mul => {
                     // Add the overflow and underflow expression checks
-                    wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
+                    let check = try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue));
+                    wp = add_check(&wp, check, "possible overflow", span, data);
                     BinaryOperator::Multiplication
                 },
-                BinOp::Div => {
-                    // Add the overflow and underflow expression checks, if operands are signed
-                    if determine_evaluation_type(&rvalue).starts_with('i') {
-                        wp = overflow::overflow_check(&wp, &var, binop, &lvalue, &rvalue);
-                    }
-                    // Add the division by 0 expression check
-                    wp = add_zero_check(&wp, &rvalue);
-                    BinaryOperator::Division
-                },
-                BinOp::Rem => {
-                    // Add the division by 0 expression check
-                    wp = add_zero_check(&wp, &rvalue);
-                    BinaryOperator::Modulo
-                },
+                // Division by zero and overflow are checked by the `Assert` terminators MIR puts
+                // before every division and remainder
+                BinOp::Div => BinaryOperator::Division,
+                BinOp::Rem => BinaryOperator::Modulo,
                 BinOp::BitOr => BinaryOperator::BitwiseOr,
                 BinOp::BitAnd => BinaryOperator::BitwiseAnd,
                 BinOp::BitXor => BinaryOperator::BitwiseXor,
-                BinOp::Shl => BinaryOperator::BitwiseLeftShift,
-                BinOp::Shr => BinaryOperator::BitwiseRightShift,
+                BinOp::Shl => {
+                    // Add the shift amount check
+                    let check = try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue));
+                    wp = add_check(&wp, check, "possible overflow", span, data);
+                    BinaryOperator::BitwiseLeftShift
+                },
+                BinOp::Shr => {
+                    // Add the shift amount check
+                    let check = try!(overflow::no_overflow(&var, binop, &lvalue, &rvalue));
+                    wp = add_check(&wp, check, "possible overflow", span, data);
+                    BinaryOperator::BitwiseRightShift
+                },
                 BinOp::Lt => BinaryOperator::LessThan,
                 BinOp::Le => BinaryOperator::LessThanOrEqual,
                 BinOp::Gt => BinaryOperator::GreaterThan,
@@ -390,7 +1207,7 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
         },
         // Generates Rvalue to a UnaryOp
         Rvalue::UnaryOp(ref unop, ref val) => {
-            let exp: Expression = gen_expression(val, data);
+            let exp: Expression = try!(gen_expression(val, data));
             let op: UnaryOperator = match *unop {
                 UnOp::Not => {
                     if determine_evaluation_type(&exp) == "bool" {
@@ -399,7 +1216,14 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
                         UnaryOperator::BitwiseNot
                     }
                 },
-                UnOp::Neg => UnaryOperator::Negation,
+                UnOp::Neg => {
+                    // Negating the minimum value of a signed type overflows
+                    if determine_evaluation_type(&exp).starts_with('i') {
+                        let check = try!(overflow::no_negation_overflow(&exp));
+                        wp = add_check(&wp, check, "possible overflow", span, data);
+                    }
+                    UnaryOperator::Negation
+                },
             };
             // push the ne new exp onto the expression: Vec<>
             expression.push(Expression::UnaryExpression( UnaryExpressionData {
@@ -407,42 +1231,113 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
                 e: Box::new(exp)
             } ));
         },
+        // Copying a reference copies the place it refers to
+        Rvalue::Use(Operand::Consume(ref source)) if var.var_type.starts_with('&') => {
+            var.var_type = referent_type(&var.var_type);
+            let place = try!(gen_lvalue(source.clone().deref(), data));
+            substitute_variable_fields(&mut wp, &var.name, &place.name);
+            expression.push(Expression::VariableMapping(place));
+        },
         //  FIXME: need def
         Rvalue::Use(ref operand) => {
-            expression.push(gen_expression(operand, data));
+            // Copying an enum copies its discriminant and the fields of each variant, and copying
+            // a struct copies its fields
+            if data.enum_variants.contains_key(&var.var_type)
+               || data.struct_fields.contains_key(&var.var_type) {
+                if let Operand::Consume(ref l) = *operand {
+                    let source = try!(gen_lvalue(l.clone(), data));
+                    substitute_variable_fields(&mut wp, &var.name, &source.name);
+                }
+            }
+            expression.push(try!(gen_expression(operand, data)));
         },
         //  FIXME: need def
         Rvalue::Aggregate(ref ag_kind, ref vec_operand) => {
             match *ag_kind {
-                AggregateKind::Tuple => {
-                    for operand in vec_operand.iter() {
-                        let e = Expression::VariableMapping( VariableMappingData {
-                            //name: var.name.as_str().to_string() + "." + i.to_string().as_str(),
-                            name: format!("{:?}", operand),
-                            var_type: gen_ty(operand, data)
-                        } );
-                        expression.push(e);
+                AggregateKind::Tuple => try!(gen_fields(&mut wp, &var, vec_operand, data)),
+                // Adt(ref adt_def, ref variant, ref substs)
+                AggregateKind::Adt(ref adt_def, variant, _) if adt_def.adt_kind() == AdtKind::Enum => {
+                    // Record which variant was constructed
+                    substitute_variable_with_expression(
+                        &mut wp,
+                        &discriminant(&var),
+                        &Expression::UnsignedBitVector( UnsignedBitVectorData {
+                            size: 64,
+                            value: variant as u64,
+                            high: 0
+                        } )
+                    );
+                    // Then each of the variant's fields
+                    let variant_name = adt_def.variants[variant].name.as_str().to_string();
+                    for (index, operand) in vec_operand.iter().enumerate() {
+                        let field = VariableMappingData {
+                            name: format!("{}.{}.{}", var.name, variant_name, index),
+                            var_type: try!(gen_ty(operand, data))
+                        };
+                        substitute_variable_with_expression(
+                            &mut wp,
+                            &field,
+                            &try!(gen_expression(operand, data))
+                        );
                     }
                 },
-                _ => rp_error!("Unsupported aggregate: only tuples are supported"),
+                // Adt(ref adt_def, ref variant, ref substs)
+                AggregateKind::Adt(ref adt_def, ..) if adt_def.adt_kind() == AdtKind::Struct => {
+                    try!(gen_fields(&mut wp, &var, vec_operand, data));
+                },
+                _ => rp_bail!("Unsupported aggregate: only tuples, structs and enums are supported"),
             }
         },
-        // FIXME: need def
-        // Cast(ref cast_kind, ref cast_operand, ref cast_ty)
-        Rvalue::Cast(..) => {
-            expression.push(Expression::VariableMapping(var.clone()));
+        // Integer and boolean to integer casts
+        Rvalue::Cast(CastKind::Misc, ref operand, cast_ty)
+            if integer_width(&type_name(cast_ty)).is_some() => {
+            let exp = try!(gen_expression(operand, data));
+            let from = determine_evaluation_type(&exp);
+            if integer_width(&from).is_none() && from != "bool" {
+                rp_bail!("Unsupported cast of {} to {}", from, type_name(cast_ty));
+            }
+            expression.push(Expression::CastExpression( CastExpressionData {
+                e: Box::new(exp),
+                var_type: type_name(cast_ty)
+            } ));
+        },
+        // Pointer, function, unsizing and float casts
+        Rvalue::Cast(ref cast_kind, _, cast_ty) => {
+            rp_bail!("Unsupported cast ({:?}) to {}", cast_kind, type_name(cast_ty));
         },
-        // FIXME: need def
+        // A reference stands for the value it refers to. Writes through it are writes to the
+        // place it borrows, see `record_references`
         // Ref(ref ref_region, ref ref_borrow_kind, ref ref_lvalue) => {
-        Rvalue::Ref(..) => {
-            expression.push(Expression::VariableMapping(var.clone()));
+        Rvalue::Ref(_, _, ref place) => {
+            var.var_type = referent_type(&var.var_type);
+            let value = try!(gen_expression(&Operand::Consume(place.clone()), data));
+            if let Expression::VariableMapping(ref v) = value {
+                substitute_variable_fields(&mut wp, &var.name, &v.name);
+            }
+            expression.push(value);
         },
-        // Unimplemented Rvalues
-        Rvalue::Box(..) => unimplemented!(),
-        Rvalue::Len(..) => unimplemented!(),
-        _ => unimplemented!(),
+        // Unsupported Rvalues
+        Rvalue::Box(..) => rp_bail!("Unsupported box allocation"),
+        Rvalue::Len(ref lvalue) => {
+            let array = try!(gen_lvalue(lvalue.clone(), data));
+            expression.push(length_of(&array));
+        },
+        // Repeat and InlineAsm
+        ref rvalue => rp_bail!("Unsupported expression {:?}", rvalue),
     };
 
+    // The element is stored into its array, which is what is assigned
+    if let Some((array, index)) = store {
+        expression = expression.into_iter().map(|value| {
+            Expression::ArrayStore( ArrayStoreData {
+                array: Box::new(Expression::VariableMapping(array.clone())),
+                index: Box::new(index.clone()),
+                value: Box::new(value)
+            } )
+        }).collect();
+        var = array;
+    }
+
     // Replace any appearance of var in the weakest precondition with the expression
     for expr in &expression {
         substitute_variable_with_expression( &mut wp, &var, expr );
@@ -451,7 +1346,40 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
     if debug {
         println!("new expression\t\t{:?}\n--------------------------------", wp.clone());
     }
-    return Some(wp);
+    return Ok(wp);
+}
+
+/// Assigns each field of a tuple or struct value its operand in the weakest precondition.
+///
+/// # Arguments:
+/// * `wp` - The weakest precondition following the assignment.
+/// * `var` - The tuple or struct being assigned.
+/// * `operands` - The value of each field, in order.
+/// * `data` - Contains the `BasicBlockData` and all argument, temp, and variable declarations from
+///            the MIR pass.
+///
+/// # Remarks:
+/// * Fields are named by their position, ie: `return.1` for the second field of the returned
+///   value, the same as they are named in conditions once field names are resolved.
+///
+fn gen_fields(wp: &mut Expression,
+              var: &VariableMappingData,
+              operands: &Vec<Operand>,
+              data: &mut MirData)
+              -> Result<(), Error> {
+    for (index, operand) in operands.iter().enumerate() {
+        let value = try!(gen_expression(operand, data));
+        let field = VariableMappingData {
+            name: format!("{}.{}", var.name, index),
+            var_type: determine_evaluation_type(&value)
+        };
+        // A field holding a tuple, struct or enum is assigned each of its fields
+        if let Expression::VariableMapping(ref source) = value {
+            substitute_variable_fields(wp, &field.name, &source.name);
+        }
+        substitute_variable_with_expression(wp, &field, &value);
+    }
+    Ok(())
 }
 
 /// Generates an appropriate variable mapping based on whatever variable, temp, or field is found
@@ -462,32 +1390,29 @@ fn gen_stmt(mut wp: Expression, stmt: Statement, data: &mut MirData, debug: bool
 ///            the MIR pass.
 ///
 /// # Return Value:
-/// * Returns a `VariableMappingData` that is built from `data` and `lvalue`
+/// * Returns a `VariableMappingData` that is built from `data` and `lvalue`, or an error if the
+///   place is not supported
 ///
 /// # Remarks:
+/// * Fields are named after the variable they belong to, ie: `tmp1.0` or `x.Some.0` for the
+///   first field of the `Some` variant of `x`.
 ///
-fn gen_lvalue(lvalue: Lvalue, data: &mut MirData) -> VariableMappingData {
-    match lvalue {
+fn gen_lvalue(lvalue: Lvalue, data: &mut MirData) -> Result<VariableMappingData, Error> {
+    Ok(match lvalue {
         // Function argument
         Lvalue::Arg(ref arg) => {
             // Find the name and type in the declaration
             VariableMappingData{
                 name: data.arg_data[arg.index()].debug_name.as_str().to_string(),
-                var_type: data.arg_data[arg.index()].ty.clone().to_string()
+                var_type: type_name(data.arg_data[arg.index()].ty)
             }
         },
         // Temporary variable
         Lvalue::Temp(ref temp) => {
             // Find the index and type in the declaration
-            let mut ty = data.temp_data[temp.index()].ty.clone().to_string();
-            if let TypeVariants::TyTuple(t) = data.temp_data[temp.index()].ty.sty {
-                if t.len() > 0 {
-                    ty = t[0].to_string();
-                }
-            }
             VariableMappingData{
                 name: "tmp".to_string() + temp.index().to_string().as_str(),
-                var_type: ty
+                var_type: type_name(data.temp_data[temp.index()].ty)
             }
         },
         // Local variable
@@ -495,7 +1420,7 @@ fn gen_lvalue(lvalue: Lvalue, data: &mut MirData) -> VariableMappingData {
             // Find the name and type in the declaration
             VariableMappingData{
                 name: "var".to_string() + var.index().to_string().as_str(),
-                var_type: data.var_data[var.index()].ty.clone().to_string()
+                var_type: type_name(data.var_data[var.index()].ty)
             }
         },
         // The returned value
@@ -505,77 +1430,86 @@ fn gen_lvalue(lvalue: Lvalue, data: &mut MirData) -> VariableMappingData {
                 var_type: data.func_return_type.clone()
             }
         },
-        // (Most likely) a field of a tuple from a checked operation
+        // A field of a tuple (most likely from a checked operation) or of an enum variant
         Lvalue::Projection(pro) => {
+            // Get the variable being projected
+            let base = try!(gen_lvalue(pro.as_ref().base.clone(), data));
 
-            // Get the index
-            let index: String = match pro.as_ref().elem.clone() {
-                // Index(ref o)
-                ProjectionElem::Index(_) => unimplemented!(),
+            match pro.as_ref().elem.clone() {
                 // Field(ref field, ref ty)
-                ProjectionElem::Field(ref field, _) => { (field.index() as i32).to_string() }
-                _ => unimplemented!(),
-            };
-
-            // Get the name of the variable being projected
-            let lvalue_name;
-            let lvalue_type;
-
-            match pro.as_ref().base {
-                // Argument
-                Lvalue::Arg(ref arg) => {
-                    // Return the name of the argument
-                    lvalue_name = data.arg_data[arg.index()].debug_name.as_str().to_string();
-                    lvalue_type = data.arg_data[arg.index()].ty.clone().to_string();
+                ProjectionElem::Field(ref field, ty) => {
+                    VariableMappingData{
+                        name: base.name + "." + field.index().to_string().as_str(),
+                        var_type: type_name(ty)
+                    }
                 },
-                // Temporary variable
-                Lvalue::Temp(ref temp) => {
-                    // Return "temp<index>"
-                    lvalue_name = "tmp".to_string() + temp.index().to_string().as_str();
-                    // warning: value assigned to `lvalue_type` is never read
-                    // lvalue_type = data.temp_data[temp.index()].ty.clone().to_string();
-                    match data.temp_data[temp.index()].ty.sty {
-                        TypeVariants::TyTuple(t) => { lvalue_type = t[0].to_string(); },
-                        _ => unimplemented!(),
+                // Downcast(ref adt_def, ref variant)
+                ProjectionElem::Downcast(ref adt_def, variant) => {
+                    // The enum value viewed as one of its variants, whose fields follow
+                    VariableMappingData{
+                        name: base.name + "." + adt_def.variants[variant].name.as_str().to_string().as_str(),
+                        var_type: base.var_type
                     }
                 },
-                // Local variable
-                Lvalue::Var(ref var) => {
-                    // Return the name of the variable
-                    let i = index.parse::<usize>().unwrap();
-                    lvalue_name = "var".to_string() + var.index().to_string().as_str();
-                    match data.var_data[var.index()].ty.sty {
-                        TypeVariants::TyTuple(t) => {
-                            lvalue_type = t[i].to_string();
-                        },
-                        _ => unimplemented!(),
+                // Elements of arrays are expressions rather than variables, see `gen_expression`
+                // and `gen_stmt`
+                ProjectionElem::Index(_) => {
+                    rp_bail!("Unsupported use of an element of {}", base.name);
+                },
+                // Dereferencing a reference made in the function gives the place it borrows
+                ProjectionElem::Deref if data.references.contains_key(&base.name) => {
+                    let place = data.references[&base.name].clone();
+                    try!(gen_lvalue(place, data))
+                },
+                // Any other reference (ie: an argument) stands for the value it refers to. For a
+                // `&mut` argument, its value on return is the value written through it
+                ProjectionElem::Deref if base.var_type.starts_with('&') => {
+                    VariableMappingData{
+                        name: base.name,
+                        var_type: referent_type(&base.var_type)
                     }
                 },
-                // Unimplemented Lvalue
-                Lvalue::ReturnPointer => unimplemented!(),
-                // Static(ref stat)
-                Lvalue::Static(_) => unimplemented!(),
-                // Multiply-nested projection
-                Lvalue::Projection(_) => unimplemented!(),
-            };
-
-            // Get the index
-            let index: String = match pro.as_ref().elem.clone() {
+                ref elem => rp_bail!("Unsupported projection {:?} of {}", elem, base.name),
+            }
+        },
+        Lvalue::Static(def_id) => rp_bail!("Unsupported use of the static {:?}", def_id),
+    })
+}
 
-                // Field(ref field, ref ty)
-                ProjectionElem::Field(ref field, _) => (field.index() as i32).to_string(),
-                // Index(ref o)
-                ProjectionElem::Index(_) => unimplemented!(),
-                _ => unimplemented!(),
-            };
+// Returns the place a dereferenced reference borrows (ie: `var0` for `(*tmp1)` after
+// `tmp1 = &mut var0`), or the lvalue itself if it is not one
+fn borrowed_place<'tcx>(lvalue: &Lvalue<'tcx>, data: &mut MirData<'tcx>)
+                       -> Result<Lvalue<'tcx>, Error> {
+    if let Lvalue::Projection(ref pro) = *lvalue {
+        if let ProjectionElem::Deref = pro.elem {
+            let base = try!(gen_lvalue(pro.base.clone(), data));
+            if let Some(place) = data.references.get(&base.name).cloned() {
+                return borrowed_place(&place, data);
+            }
+        }
+    }
+    Ok(lvalue.clone())
+}
 
-            // Get the index int from index_operand, then stick it in the VariableMappingData
-            VariableMappingData{ name: lvalue_name + "." + index.as_str(), var_type: lvalue_type }
-        },
-        _=> unimplemented!(),
+// Checks whether a projection is the index of an array
+fn is_index(elem: &LvalueElem) -> bool {
+    match *elem {
+        ProjectionElem::Index(_) => true,
+        _ => false,
     }
 }
 
+// Returns the type a reference refers to (ie: `[u32]` for `&mut [u32]`), or the type itself if it
+// is not a reference
+pub fn referent_type(var_type: &str) -> String {
+    if var_type.starts_with("&mut ") {
+        var_type[5..].to_string()
+    } else if var_type.starts_with('&') {
+        var_type[1..].to_string()
+    } else {
+        var_type.to_string()
+    }
+}
 
 /// Generates an Expression based on some operand, either a literal or some kind of variable, temp,
 /// or field
@@ -586,86 +1520,159 @@ fn gen_lvalue(lvalue: Lvalue, data: &mut MirData) -> VariableMappingData {
 ///            the MIR pass.
 ///
 /// # Return Value:
-/// * Returns a new expression generated from an operand
+/// * Returns a new expression generated from an operand, or an error if it is not supported
 ///
 /// # Remarks:
-/// * Current supported types: `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32`, `u64`, `bool`
+/// * Current supported types: `i8`, `i16`, `i32`, `i64`, `isize`, `u8`, `u16`, `u32`, `u64`,
+///   `usize`, `bool`
 ///
-fn gen_expression(operand: &Operand, data: &mut MirData) -> Expression {
+fn gen_expression(operand: &Operand, data: &mut MirData) -> Result<Expression, Error> {
     match *operand {
         // A variable/temp/field
         Operand::Consume (ref l) => {
-            Expression::VariableMapping( gen_lvalue(l.clone(), data) )
+            let l = &try!(borrowed_place(l, data));
+            // An element of an array or slice
+            if let Lvalue::Projection(ref pro) = *l {
+                if let ProjectionElem::Index(ref index) = pro.elem {
+                    let array = try!(gen_lvalue(pro.base.clone(), data));
+                    return Ok(Expression::ArrayIndex( ArrayIndexData {
+                        array: Box::new(Expression::VariableMapping(array)),
+                        index: Box::new(try!(gen_expression(index, data)))
+                    } ));
+                }
+            }
+            Ok(Expression::VariableMapping( try!(gen_lvalue(l.clone(), data)) ))
         },
         // A literal value
         Operand::Constant (ref c) => {
             match c.literal {
-                Literal::Value {ref value} => {
-                    match *value {
-                        ConstVal::Bool(ref const_bool) => {
-                            Expression::BooleanLiteral(*const_bool)
-                        }
-                        ConstVal::Integral(ref const_int) => {
-                            match *const_int {
-                                ConstInt::I8(i) => {
-                                    Expression::SignedBitVector( SignedBitVectorData {
-                                        size: 8,
-                                        value: i as i64
-                                    } )
-                                },
-                                ConstInt::I16(i) => {
-                                    Expression::SignedBitVector( SignedBitVectorData {
-                                        size: 16,
-                                        value: i as i64
-                                    } )
-                                },
-                                ConstInt::I32(i) => {
-                                    Expression::SignedBitVector( SignedBitVectorData {
-                                        size: 32,
-                                        value: i as i64
-                                    } )
-                                },
-                                ConstInt::I64(i) => {
-                                    Expression::SignedBitVector( SignedBitVectorData {
-                                        size: 64,
-                                        value: i as i64
-                                    } )
-                                },
-                                ConstInt::U8(u) => {
-                                    Expression::UnsignedBitVector( UnsignedBitVectorData {
-                                        size: 8,
-                                        value: u as u64
-                                    } )
-                                },
-                                ConstInt::U16(u) => {
-                                    Expression::UnsignedBitVector( UnsignedBitVectorData {
-                                        size: 16,
-                                        value: u as u64
-                                    } )
-                                },
-                                ConstInt::U32(u) => {
-                                    Expression::UnsignedBitVector( UnsignedBitVectorData {
-                                        size: 32,
-                                        value: u as u64
-                                    } )
-                                },
-                                ConstInt::U64(u) => {
-                                    Expression::UnsignedBitVector( UnsignedBitVectorData {
-                                        size: 64,
-                                        value: u as u64
-                                    } )
-                                },
-                                _ => unimplemented!(),
-                            }
-                        },
-                        _ => unimplemented!(),
-                    }
-                },
+                Literal::Value {ref value} => gen_const_val(value),
                 // Item {ref def_id, ref substs}
-                Literal::Item {..} => unimplemented!(),
+                Literal::Item {..} => rp_bail!("Unsupported use of the item {:?}", c.literal),
                 // Promoted {ref index}
-                Literal::Promoted {..} => unimplemented!(),
+                Literal::Promoted {..} => rp_bail!("Unsupported promoted constant {:?}", c.literal),
             }
         },
     }
 }
+
+/// Generates an Expression from a constant value.
+///
+/// # Arguments:
+/// * `value` - The constant value, either a boolean or an integer.
+///
+/// # Return Value:
+/// * Returns a new literal expression, or an error if the constant is not supported.
+///
+/// # Remarks:
+/// * Current supported types: `i8`, `i16`, `i32`, `i64`, `isize`, `u8`, `u16`, `u32`, `u64`,
+///   `usize`, `bool`
+///
+fn gen_const_val(value: &ConstVal) -> Result<Expression, Error> {
+    Ok(match *value {
+        ConstVal::Bool(ref const_bool) => {
+            Expression::BooleanLiteral(*const_bool)
+        }
+        ConstVal::Integral(ref const_int) => {
+            match *const_int {
+                ConstInt::I8(i) => {
+                    Expression::SignedBitVector( SignedBitVectorData {
+                        size: 8,
+                        value: i as i64,
+                        high: 0
+                    } )
+                },
+                ConstInt::I16(i) => {
+                    Expression::SignedBitVector( SignedBitVectorData {
+                        size: 16,
+                        value: i as i64,
+                        high: 0
+                    } )
+                },
+                ConstInt::I32(i) => {
+                    Expression::SignedBitVector( SignedBitVectorData {
+                        size: 32,
+                        value: i as i64,
+                        high: 0
+                    } )
+                },
+                ConstInt::I64(i) => {
+                    Expression::SignedBitVector( SignedBitVectorData {
+                        size: 64,
+                        value: i as i64,
+                        high: 0
+                    } )
+                },
+                ConstInt::U8(u) => {
+                    Expression::UnsignedBitVector( UnsignedBitVectorData {
+                        size: 8,
+                        value: u as u64,
+                        high: 0
+                    } )
+                },
+                ConstInt::U16(u) => {
+                    Expression::UnsignedBitVector( UnsignedBitVectorData {
+                        size: 16,
+                        value: u as u64,
+                        high: 0
+                    } )
+                },
+                ConstInt::U32(u) => {
+                    Expression::UnsignedBitVector( UnsignedBitVectorData {
+                        size: 32,
+                        value: u as u64,
+                        high: 0
+                    } )
+                },
+                ConstInt::U64(u) => {
+                    Expression::UnsignedBitVector( UnsignedBitVectorData {
+                        size: 64,
+                        value: u as u64,
+                        high: 0
+                    } )
+                },
+                // isize and usize constants take the configured pointer width, which may differ
+                // from the width they were compiled with
+                ConstInt::Isize(i) => {
+                    let value = match i {
+                        ConstIsize::Is32(i) => i as i64,
+                        ConstIsize::Is64(i) => i,
+                    };
+                    let width = pointer_width();
+                    if width < 64 && (value >> (width - 1) != 0 && value >> (width - 1) != -1) {
+                        rp_bail!("Constant {}isize does not fit in {} bits", value, width);
+                    }
+                    pointer_sized(Expression::SignedBitVector( SignedBitVectorData {
+                        size: width,
+                        value: value as i64,
+                        high: 0
+                    } ), "isize")
+                },
+                ConstInt::Usize(u) => {
+                    let value = match u {
+                        ConstUsize::Us32(u) => u as u64,
+                        ConstUsize::Us64(u) => u,
+                    };
+                    let width = pointer_width();
+                    if width < 64 && value >> width != 0 {
+                        rp_bail!("Constant {}usize does not fit in {} bits", value, width);
+                    }
+                    pointer_sized(Expression::UnsignedBitVector( UnsignedBitVectorData {
+                        size: width,
+                        value: value as u64,
+                        high: 0
+                    } ), "usize")
+                },
+                // The pinned toolchain has no 128-bit integers, so their constants cannot be
+                // matched by name
+                ref const_int if format!("{:?}", const_int).starts_with("I128") ||
+                                 format!("{:?}", const_int).starts_with("U128") => {
+                    rp_bail!("Unsupported constant {:?}: i128 and u128 are only supported in \
+                              conditions", const_int);
+                },
+                ref const_int => rp_bail!("Unsupported constant {:?}", const_int),
+            }
+        },
+        ref value => rp_bail!("Unsupported constant {:?}", value),
+    })
+}
@@ -14,33 +14,99 @@ extern crate rustc_const_math;
 
 use expression::*;
 use rustc::mir::repr::*;
+use reporting::Error;
 
 
 /// Routes to appropriate overflow check (signed / unsigned)
-// One catch-all function for overflow checking.
-pub fn overflow_check(wp: &Expression,
-                      var: &VariableMappingData,
-                      binop: &BinOp,
-                      lvalue: &Expression,
-                      rvalue: &Expression)
-                      -> Expression {
-    let v = var.clone();
+// Returns an Expression that is true when lvalue (binop) rvalue does not overflow or underflow the
+// type of var, the variable the result is assigned to
+pub fn no_overflow(var: &VariableMappingData,
+                   binop: &BinOp,
+                   lvalue: &Expression,
+                   rvalue: &Expression)
+                   -> Result<Expression, Error> {
+    // Shifts overflow when the amount is not less than the width, whatever the signedness
+    if let BinOp::Shl | BinOp::Shr = *binop {
+        return shift_in_range(var, rvalue);
+    }
+    Ok(match var.var_type.as_str() {
+        "i8" => signed_overflow(binop, 8u8, lvalue, rvalue),
+        "i16" => signed_overflow(binop, 16u8, lvalue, rvalue),
+        "i32" => signed_overflow(binop, 32u8, lvalue, rvalue),
+        "i64" => signed_overflow(binop, 64u8, lvalue, rvalue),
+        "i128" => signed_overflow(binop, 128u8, lvalue, rvalue),
+        // isize is checked as the signed integer type of the pointer width
+        "isize" => {
+            let fixed_type = format!("i{}", pointer_width());
+            signed_overflow(binop, pointer_width(), &cast_to(lvalue, &fixed_type),
+                            &cast_to(rvalue, &fixed_type))
+        },
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            unsigned_overflow(binop, lvalue, rvalue)
+        },
+        _ => rp_bail!("Unsupported return type of binary operation: {}", var.var_type),
+    })
+}
 
-    Expression::BinaryExpression( BinaryExpressionData {
-        op: BinaryOperator::And,
-        left: Box::new(wp.clone()),
-        right: Box::new(
-            match v.var_type.as_str() {
-                "i8" => signed_overflow(binop, 8u8, lvalue, rvalue),
-                "i16" => signed_overflow(binop, 16u8, lvalue, rvalue),
-                "i32" => signed_overflow(binop, 32u8, lvalue, rvalue),
-                "i64" => signed_overflow(binop, 64u8, lvalue, rvalue),
-                "u8" | "u16" | "u32" | "u64" => {
-                    unsigned_overflow(binop, lvalue, rvalue)
-                },
-                _ => panic!("Unsupported return type of binary operation: {}", v.var_type),
-            }
-        ),
+/// Creates an Expression that is true when a shift amount is less than the width of the value
+/// shifted, which Rust requires
+///
+/// The following psuedocode provides a logically equivalent version of what is produced
+/// (false is returned if the shift overflows, true otherwise)
+///
+/// ```psuedo
+/// rvalue as u128 < width
+/// ```
+///
+/// The amount is widened to 128 bits first, so it can be of any integer type. A negative amount
+/// becomes a large unsigned amount, as Rust treats it.
+fn shift_in_range(var: &VariableMappingData, rvalue: &Expression) -> Result<Expression, Error> {
+    let width = match integer_width(&var.var_type) {
+        Some(width) => width,
+        None => rp_bail!("Unsupported return type of binary operation: {}", var.var_type),
+    };
+    Ok(Expression::BinaryExpression( BinaryExpressionData {
+        op: BinaryOperator::LessThan,
+        left: Box::new(Expression::CastExpression( CastExpressionData {
+            e: Box::new(rvalue.clone()),
+            var_type: "u128".to_string()
+        } )),
+        right: Box::new(Expression::UnsignedBitVector( UnsignedBitVectorData {
+            size: 128,
+            value: width as u64,
+            high: 0
+        } ))
+    } ))
+}
+
+/// Creates an Expression that is true when negating a signed value does not overflow
+///
+/// The following psuedocode provides a logically equivalent version of what is produced
+/// (false is returned if the negation overflows, true otherwise)
+///
+/// ```psuedo
+/// value != MIN
+/// ```
+pub fn no_negation_overflow(value: &Expression) -> Result<Expression, Error> {
+    let var_type = determine_evaluation_type(value);
+    let size = match integer_width(&var_type) {
+        Some(width) if var_type.starts_with('i') => width,
+        _ => rp_bail!("Unsupported type of negation: {}", var_type),
+    };
+    // MIN is a literal of the width, so an isize is compared as the integer type of that width
+    Ok(Expression::BinaryExpression( BinaryExpressionData{
+        op: BinaryOperator::NotEqual,
+        left: Box::new(cast_to(value, &format!("i{}", size))),
+        right: Box::new(signed_min(size)),
+    }))
+}
+
+// Returns the minimum value of the signed integer type of the given size
+fn signed_min(size: u8) -> Expression {
+    Expression::SignedBitVector( SignedBitVectorData{
+        size: size,
+        value: if size > 64 { 0 } else { i64::min_value() >> (64 - size) },
+        high: if size > 64 { i64::min_value() } else { 0 }
     })
 }
 
@@ -52,19 +118,13 @@ fn signed_overflow(binop: &BinOp, size: u8, lvalue: &Expression, rvalue: &Expres
         BinOp::Add => signed_add(size, lvalue, rvalue),
         BinOp::Mul => signed_mul(lvalue, rvalue),
         BinOp::Sub => signed_sub(size, lvalue, rvalue),
-        BinOp::Div => signed_div(size, lvalue, rvalue),
-        BinOp::Rem => unimplemented!(),
-        BinOp::Shl => unimplemented!(),
-        BinOp::Shr => unimplemented!(),
-        BinOp::BitOr => unimplemented!(),
-        BinOp::BitAnd => unimplemented!(),
-        BinOp::BitXor => unimplemented!(),
-        BinOp::Lt => unimplemented!(),
-        BinOp::Le => unimplemented!(),
-        BinOp::Gt => unimplemented!(),
-        BinOp::Ge => unimplemented!(),
-        BinOp::Eq => unimplemented!(),
-        BinOp::Ne => unimplemented!(),
+        // Division and remainder are checked by the `Assert` terminators before them
+        BinOp::Div | BinOp::Rem => unreachable!(),
+        // Shifts are checked by `shift_in_range`
+        BinOp::Shl | BinOp::Shr => unreachable!(),
+        // Only arithmetic operations are checked, see `gen_stmt`
+        BinOp::BitOr | BinOp::BitAnd | BinOp::BitXor |
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge | BinOp::Eq | BinOp::Ne => unreachable!(),
     }
 }
 
@@ -105,7 +165,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -117,7 +178,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -137,7 +199,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                         right: Box::new(
                             Expression::SignedBitVector( SignedBitVectorData {
                                 size: size,
-                                value: 0i64,
+                                value: 0,
+                                high: 0
                             })
                         ),
                     })
@@ -157,7 +220,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -169,7 +233,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -189,7 +254,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                         right: Box::new(
                                             Expression::SignedBitVector( SignedBitVectorData {
                                                 size: size,
-                                                value: 0i64,
+                                                value: 0,
+                                                high: 0
                                             })
                                         ),
                                     })
@@ -201,7 +267,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                         right: Box::new(
                                             Expression::SignedBitVector( SignedBitVectorData {
                                                 size: size,
-                                                value: 0i64,
+                                                value: 0,
+                                                high: 0
                                             })
                                         ),
                                     })
@@ -221,7 +288,8 @@ fn signed_add(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -270,7 +338,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -282,7 +351,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -302,7 +372,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                         right: Box::new(
                             Expression::SignedBitVector( SignedBitVectorData {
                                 size: size,
-                                value: 0i64,
+                                value: 0,
+                                high: 0
                             })
                         ),
                     })
@@ -322,7 +393,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -334,7 +406,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -354,7 +427,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                         right: Box::new(
                                             Expression::SignedBitVector( SignedBitVectorData {
                                                 size: size,
-                                                value: 0i64,
+                                                value: 0,
+                                                high: 0
                                             })
                                         ),
                                     })
@@ -366,7 +440,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                         right: Box::new(
                                             Expression::SignedBitVector( SignedBitVectorData {
                                                 size: size,
-                                                value: 0i64,
+                                                value: 0,
+                                                high: 0
                                             })
                                         ),
                                     })
@@ -386,7 +461,8 @@ fn signed_sub(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression
                                 right: Box::new(
                                     Expression::SignedBitVector( SignedBitVectorData {
                                         size: size,
-                                        value: 0i64,
+                                        value: 0,
+                                        high: 0
                                     })
                                 ),
                             })
@@ -418,69 +494,6 @@ fn signed_mul(lvalue: &Expression, rvalue: &Expression) -> Expression {
     })
 }
 
-fn signed_div(size: u8, lvalue: &Expression, rvalue: &Expression) -> Expression {
-    let condition = Expression::BinaryExpression( BinaryExpressionData{
-        op: BinaryOperator::And,
-        left: Box::new(
-            Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::Equal,
-                left: Box::new(lvalue.clone()),
-                right: Box::new(
-                    Expression::SignedBitVector( SignedBitVectorData{
-                        size: size,
-                        value: match size {
-                            8u8 => i8::min_value() as i64,
-                            16u8 => i16::min_value() as i64,
-                            32u8 => i32::min_value() as i64,
-                            64u8 => i64::min_value() as i64,
-                            _ => panic!("unsupported integer type"),
-                        },
-                    })
-                ),
-            })
-        ),
-        right: Box::new(
-            Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::Equal,
-                left: Box::new(rvalue.clone()),
-                right: Box::new(
-                    Expression::SignedBitVector( SignedBitVectorData{
-                        size: size,
-                        value: -1i64,
-                    })
-                )
-            })
-        ),
-    });
-
-    Expression::BinaryExpression( BinaryExpressionData{
-        op: BinaryOperator::And,
-        left: Box::new(
-            Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::Implication,
-                left: Box::new(condition.clone()),
-                right: Box::new(
-                    Expression::BooleanLiteral(false)
-                ),
-            })
-        ),
-        right: Box::new(
-            Expression::BinaryExpression( BinaryExpressionData{
-                op: BinaryOperator::Implication,
-                left: Box::new(
-                    Expression::UnaryExpression( UnaryExpressionData{
-                        op: UnaryOperator::Not,
-                        e: Box::new(condition.clone()),
-                    })
-                ),
-                right: Box::new(
-                    Expression::BooleanLiteral(true)
-                ),
-            })
-        ),
-    })
-}
-
 /// Routes to appropriate overflow check
 // Unsigned: Match on the type of BinOp and call the correct function
 fn unsigned_overflow(binop: &BinOp, lvalue: &Expression, rvalue: &Expression) -> Expression {
@@ -488,19 +501,13 @@ fn unsigned_overflow(binop: &BinOp, lvalue: &Expression, rvalue: &Expression) ->
         BinOp::Add => unsigned_add(lvalue, rvalue),
         BinOp::Sub => unsigned_sub(lvalue, rvalue),
         BinOp::Mul => unsigned_mul(lvalue, rvalue),
-        BinOp::Div => unreachable!(),
-        BinOp::Rem => unimplemented!(),
-        BinOp::Shl => unimplemented!(),
-        BinOp::Shr => unimplemented!(),
-        BinOp::BitOr => unimplemented!(),
-        BinOp::BitAnd => unimplemented!(),
-        BinOp::BitXor => unimplemented!(),
-        BinOp::Lt => unimplemented!(),
-        BinOp::Le => unimplemented!(),
-        BinOp::Gt => unimplemented!(),
-        BinOp::Ge => unimplemented!(),
-        BinOp::Eq => unimplemented!(),
-        BinOp::Ne => unimplemented!(),
+        // Division and remainder are checked by the `Assert` terminators before them
+        BinOp::Div | BinOp::Rem => unreachable!(),
+        // Shifts are checked by `shift_in_range`
+        BinOp::Shl | BinOp::Shr => unreachable!(),
+        // Only arithmetic operations are checked, see `gen_stmt`
+        BinOp::BitOr | BinOp::BitAnd | BinOp::BitXor |
+        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge | BinOp::Eq | BinOp::Ne => unreachable!(),
     }
 }
 
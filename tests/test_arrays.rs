@@ -0,0 +1,53 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Array and Slice Tests
+// * * *
+
+// Reading an index known to be in bounds
+// Should be valid
+#[condition(pre="i: usize < 4usize", post="return: u32 == a: [u32; 4][i: usize]")]
+fn valid_array_read(a: [u32; 4], i: usize) -> u32 {
+    a[i]
+}
+
+// Reading a slice within its length
+// Should be valid
+#[condition(pre="i: usize < len(s: &[u32])", post="return: u32 == s: [u32][i: usize]")]
+fn valid_slice_read(s: &[u32], i: usize) -> u32 {
+    s[i]
+}
+
+// Writing an element of a mutable slice
+// Should be valid
+#[condition(pre="i: usize < len(s: &mut [u32])", post="s: [u32][i: usize] == 7u32")]
+fn valid_slice_write(s: &mut [u32], i: usize) {
+    s[i] = 7;
+}
+
+// Writing one element leaves the others alone
+// Should be valid
+#[condition(pre="(i: usize < 4usize) AND (j: usize < 4usize) AND (i: usize != j: usize)",
+            post="return: u32 == a: [u32; 4][j: usize]")]
+fn valid_array_write_other(mut a: [u32; 4], i: usize, j: usize) -> u32 {
+    a[i] = 0;
+    a[j]
+}
+
+// The index may be past the end of the array
+// Should be invalid
+#[condition(pre="true", post="true")]
+fn invalid_array_out_of_bounds(a: [u32; 4], i: usize) -> u32 {
+    a[i]
+}
+
+// The slice may be empty
+// Should be invalid
+#[condition(pre="true", post="true")]
+fn invalid_slice_empty(s: &[u32]) -> u32 {
+    s[0]
+}
@@ -31,3 +31,31 @@ fn invalid_simple_assertion_eq(x: i32) {
     assert_eq!(x, 0);
 }
 */
+
+// * * *
+// Compiler Assertion Tests
+// * * *
+
+// Should be valid
+#[condition(pre="x: i32 > i32::MIN", post="return: i32 == 0i32 - x: i32")]
+fn valid_negate_checked(x: i32) -> i32 {
+    -x
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: i32 == 0i32 - x: i32")]
+fn invalid_negate_overflow(x: i32) -> i32 {
+    -x
+}
+
+// Should be valid
+#[condition(pre="(y: i32 != 0i32) AND (y: i32 != -1i32)", post="true")]
+fn valid_divide_checked(x: i32, y: i32) -> i32 {
+    x / y
+}
+
+// Should be invalid
+#[condition(pre="y: i32 != 0i32", post="true")]
+fn invalid_divide_overflow(x: i32, y: i32) -> i32 {
+    x / y
+}
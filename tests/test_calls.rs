@@ -0,0 +1,101 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Function Call Tests
+// * * *
+
+#[condition(pre="x: u32 < 100u32", post="return: u32 == (x: u32 + 1u32)")]
+fn increment(x: u32) -> u32 {
+    x + 1
+}
+
+#[condition(pre="(x: i32 >= 0i32) && (y: i32 >= 0i32)", post="(return: i32 >= x: i32) && (return: i32 >= y: i32)")]
+fn max(x: i32, y: i32) -> i32 {
+    if x > y {
+        x
+    } else {
+        y
+    }
+}
+
+// Callee's precondition holds, and its postcondition gives the caller's
+// Should be valid
+#[condition(pre="x: u32 < 50u32", post="return: u32 == (x: u32 + 2u32)")]
+fn valid_increment_twice(x: u32) -> u32 {
+    let y = increment(x);
+    increment(y)
+}
+
+// Callee's precondition may not hold
+// Should be invalid
+#[condition(pre="x: u32 < 100u32", post="return: u32 == (x: u32 + 2u32)")]
+fn invalid_increment_twice(x: u32) -> u32 {
+    let y = increment(x);
+    increment(y)
+}
+
+// Callee's postcondition is too weak for the caller's
+// Should be invalid
+#[condition(pre="(a: i32 >= 0i32) && (b: i32 >= 0i32)", post="return: i32 == a: i32")]
+fn invalid_max_is_first(a: i32, b: i32) -> i32 {
+    max(a, b)
+}
+
+// Actual arguments are passed in the opposite order to the formal arguments' names
+// Should be valid
+#[condition(pre="(x: i32 >= 0i32) && (y: i32 >= 0i32)", post="return: i32 >= y: i32")]
+fn valid_max_swapped(x: i32, y: i32) -> i32 {
+    max(y, x)
+}
+
+// Callee has no condition attribute, so its result is unconstrained
+fn unannotated(x: u32) -> u32 {
+    x
+}
+
+// The caller does not depend on the opaque callee's result
+// Should be valid
+#[condition(pre="x: u32 < 100u32", post="return: u32 == (x: u32 + 1u32)")]
+fn valid_opaque_call_unused(x: u32) -> u32 {
+    let _y = unannotated(x);
+    x + 1
+}
+
+// Nothing is known about the opaque callee's result
+// Should be invalid
+#[condition(pre="x: u32 < 100u32", post="return: u32 == x: u32")]
+fn invalid_opaque_call_result(x: u32) -> u32 {
+    unannotated(x)
+}
+
+// Calls into std without a condition attribute are opaque as well
+// Should be invalid
+#[condition(pre="true", post="return: u32 <= x: u32")]
+fn invalid_opaque_std_call(x: u32, y: u32) -> u32 {
+    std::cmp::max(x, y)
+}
+
+#[condition(pre="x: Option<u32> is Some", post="true")]
+fn expect_some(x: Option<u32>) -> u32 {
+    match x {
+        Some(v) => v,
+        None => 0,
+    }
+}
+
+// Callee's precondition checks a variant of its own argument's enum
+// Should be valid
+#[condition(pre="true", post="true")]
+fn valid_call_with_variant_precondition(x: u32) -> u32 {
+    expect_some(Some(x))
+}
+
+// Should be invalid
+#[condition(pre="true", post="true")]
+fn invalid_call_with_variant_precondition() -> u32 {
+    expect_some(None)
+}
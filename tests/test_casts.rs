@@ -0,0 +1,51 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Cast Tests
+// * * *
+
+// The product of two u32 values always fits in a u64
+// Should be valid
+#[condition(pre="true", post="return: u64 == (x: u32 as u64 * y: u32 as u64)")]
+fn valid_widening_mul(x: u32, y: u32) -> u64 {
+    x as u64 * y as u64
+}
+
+// Casting a negative i8 to i32 keeps its value
+// Should be valid
+#[condition(pre="x: i8 < 0i8", post="return: i32 < 0i32")]
+fn valid_sign_extension(x: i8) -> i32 {
+    x as i32
+}
+
+// Truncating to u8 keeps the lower 8 bits
+// Should be valid
+#[condition(pre="true", post="return: u8 == ((x: u32 & 255u32) as u8)")]
+fn valid_truncation(x: u32) -> u8 {
+    x as u8
+}
+
+// true is 1 and false is 0
+// Should be valid
+#[condition(pre="true", post="return: u32 <= 1u32")]
+fn valid_bool_cast(b: bool) -> u32 {
+    b as u32
+}
+
+// Casting a negative i32 to u32 wraps around
+// Should be invalid
+#[condition(pre="true", post="return: u32 <= i32::MAX as u32")]
+fn invalid_sign_cast(x: i32) -> u32 {
+    x as u32
+}
+
+// The product of two u32 values overflows a u32
+// Should be invalid
+#[condition(pre="true", post="true")]
+fn invalid_narrow_mul(x: u32, y: u32) -> u64 {
+    (x * y) as u64
+}
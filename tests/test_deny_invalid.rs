@@ -0,0 +1,13 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof(deny))]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// Used to test the deny plugin argument
+
+// Condition is not valid, so compilation should fail
+#[condition(pre="true", post="return: i32 == (x: i32 + 5i32)")]
+fn invalid_add_five_i32(x: i32) -> i32 {
+    x+5
+}
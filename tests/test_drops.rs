@@ -0,0 +1,65 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+#![allow(unused_variables)]
+#![allow(unused_assignments)]
+fn main() { }
+
+enum Direction {
+    Up,
+    Down,
+}
+
+// * * *
+// Drop and Unreachable Tests
+// * * *
+
+// Should be valid
+#[condition(pre="x: u32 < 10u32", post="return: u32 == x: u32 + 1u32")]
+fn valid_drop_local(x: u32) -> u32 {
+    let s = String::new();
+    x + 1
+}
+
+// Should be valid
+#[condition(pre="x: u32 < 10u32", post="return: u32 == x: u32 + 2u32")]
+fn valid_drop_and_replace(x: u32) -> u32 {
+    let mut s = String::new();
+    s = String::new();
+    x + 2
+}
+
+// Should be valid
+#[condition(pre="true", post="(return: i8 == 1i8) OR (return: i8 == -1i8)")]
+fn valid_exhaustive_match(d: Direction) -> i8 {
+    match d {
+        Direction::Up => 1,
+        Direction::Down => -1,
+    }
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: u32 == x: u32 + 1u32")]
+fn invalid_drop_local(x: u32) -> u32 {
+    let s = String::new();
+    x + 1
+}
+
+// Should be invalid
+// The overflow check of the addition unwinds to a cleanup block that drops s and resumes
+// unwinding. The failed check is what is reported; the unwinding after it is not followed
+#[condition(pre="x: u32 < 10u32", post="return: u32 == x: u32 + y: u32")]
+fn invalid_overflow_before_resume(x: u32, y: u32) -> u32 {
+    let s = String::new();
+    x + y
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: i8 == 1i8")]
+fn invalid_exhaustive_match(d: Direction) -> i8 {
+    match d {
+        Direction::Up => 1,
+        Direction::Down => -1,
+    }
+}
@@ -0,0 +1,15 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// A loop without an invariant is reported as an error at the loop
+#[condition(pre="n: u32 <= 1000u32", post="return: u32 == n: u32")]
+fn valid_count_up_no_invariant(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
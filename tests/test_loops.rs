@@ -0,0 +1,119 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Loop Invariant Tests
+// * * *
+
+// Counts up to n, invariant holds on entry, is preserved, and gives the postcondition on exit
+// Should be valid
+#[condition(pre="n: u32 <= 1000u32",
+            post="return: u32 == n: u32",
+            inv="(i: u32 <= n: u32) && (n: u32 <= 1000u32)")]
+fn valid_count_up(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+// Counts down from n to zero
+// Should be valid
+#[condition(pre="true", post="return: u32 == 0u32", inv="true")]
+fn valid_count_down(n: u32) -> u32 {
+    let mut i = n;
+    while i > 0 {
+        i -= 1;
+    }
+    i
+}
+
+// Invariant is too weak to establish the postcondition on exit
+// Should be invalid
+#[condition(pre="n: u32 <= 1000u32",
+            post="return: u32 == n: u32",
+            inv="n: u32 <= 1000u32")]
+fn invalid_count_up_weak_invariant(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+// Invariant does not hold on entry to the loop
+// Should be invalid
+#[condition(pre="n: u32 <= 1000u32",
+            post="return: u32 == n: u32",
+            inv="(i: u32 <= n: u32) && (i: u32 > 0u32)")]
+fn invalid_count_up_entry(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+// Invariant is not preserved, since i can overshoot n
+// Should be invalid
+#[condition(pre="n: u32 <= 1000u32",
+            post="return: u32 >= n: u32",
+            inv="(i: u32 <= n: u32) && (n: u32 <= 1000u32)")]
+fn invalid_count_up_by_two(n: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 2;
+    }
+    i
+}
+
+// Two sequential loops, the second with a loop nested in it. Each invariant belongs to the loop
+// in the same position in the source
+// Should be valid
+#[condition(pre="(n: u32 <= 100u32) && (m: u32 <= 100u32)",
+            post="return: u32 == n: u32 + m: u32",
+            inv="(i: u32 <= n: u32) && (n: u32 <= 100u32) && (m: u32 <= 100u32)",
+            inv="(k: u32 <= m: u32) && (i: u32 == n: u32) && (n: u32 <= 100u32) && (m: u32 <= 100u32)",
+            inv="(j: u32 <= n: u32) && (k: u32 < m: u32) && (i: u32 == n: u32) && (n: u32 <= 100u32) && (m: u32 <= 100u32)")]
+fn valid_sequential_and_nested_loops(n: u32, m: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    let mut k = 0;
+    while k < m {
+        let mut j = 0;
+        while j < n {
+            j += 1;
+        }
+        k += 1;
+    }
+    i + k
+}
+
+// The same loops with the invariants of the outer and nested loop swapped
+// Should be invalid
+#[condition(pre="(n: u32 <= 100u32) && (m: u32 <= 100u32)",
+            post="return: u32 == n: u32 + m: u32",
+            inv="(i: u32 <= n: u32) && (n: u32 <= 100u32) && (m: u32 <= 100u32)",
+            inv="(j: u32 <= n: u32) && (k: u32 < m: u32) && (i: u32 == n: u32) && (n: u32 <= 100u32) && (m: u32 <= 100u32)",
+            inv="(k: u32 <= m: u32) && (i: u32 == n: u32) && (n: u32 <= 100u32) && (m: u32 <= 100u32)")]
+fn invalid_sequential_and_nested_loops_swapped(n: u32, m: u32) -> u32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    let mut k = 0;
+    while k < m {
+        let mut j = 0;
+        while j < n {
+            j += 1;
+        }
+        k += 1;
+    }
+    i + k
+}
@@ -0,0 +1,128 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Integer Match Tests
+// * * *
+
+// Should be valid
+#[condition(pre="x: u32 == 1u32", post="return: u32 == 10u32")]
+fn valid_match_literal_arm(x: u32) -> u32 {
+    match x {
+        0 => 5,
+        1 => 10,
+        _ => 15,
+    }
+}
+
+// Should be valid
+#[condition(pre="x: i32 > 1i32", post="return: i32 == x: i32")]
+fn valid_match_otherwise_arm(x: i32) -> i32 {
+    match x {
+        0 => 5,
+        1 => 10,
+        _ => x,
+    }
+}
+
+// Should be valid
+#[condition(pre="true", post="return: u8 <= 2u8")]
+fn valid_match_all_arms(x: u8) -> u8 {
+    match x {
+        0 => 0,
+        1 => 1,
+        _ => 2,
+    }
+}
+
+// Should be invalid
+#[condition(pre="x: u32 <= 1u32", post="return: u32 == 10u32")]
+fn invalid_match_literal_arm(x: u32) -> u32 {
+    match x {
+        0 => 5,
+        1 => 10,
+        _ => 15,
+    }
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: i32 != 0i32")]
+fn invalid_match_otherwise_arm(x: i32) -> i32 {
+    match x {
+        0 => 5,
+        1 => 10,
+        _ => x,
+    }
+}
+
+// * * *
+// Enum Match Tests
+// * * *
+
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+// Should be valid
+#[condition(pre="c: Color is Green", post="return: u8 == 2u8")]
+fn valid_match_color(c: Color) -> u8 {
+    match c {
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Blue => 3,
+    }
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: u8 < 3u8")]
+fn invalid_match_color(c: Color) -> u8 {
+    match c {
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Blue => 3,
+    }
+}
+
+// Should be valid
+#[condition(pre="x: Option<u32> is None", post="return: u32 == 0u32")]
+fn valid_match_option_none(x: Option<u32>) -> u32 {
+    match x {
+        Some(v) => v,
+        None => 0,
+    }
+}
+
+// Should be valid
+#[condition(pre="x: u32 < 10u32", post="return: Option<u32> is Some")]
+fn valid_construct_option(x: u32) -> Option<u32> {
+    if x < 10 {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: Option<u32> is Some")]
+fn invalid_construct_option(x: u32) -> Option<u32> {
+    if x < 10 {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+// Should be valid
+#[condition(pre="x: u32 < 10u32", post="return: u32 == x: u32")]
+fn valid_option_round_trip(x: u32) -> u32 {
+    let y = Some(x);
+    match y {
+        Some(v) => v,
+        None => 10,
+    }
+}
@@ -0,0 +1,33 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Old Expression Tests
+// * * *
+
+// The result is one more than the argument was on entry
+// Should be valid
+#[condition(pre="x: u32 < 100u32", post="return: u32 == (old(x: u32) + 1u32)")]
+fn valid_old_argument(mut x: u32) -> u32 {
+    x = x + 1;
+    x
+}
+
+// The argument is doubled, so its entry value is half of the result
+// Should be valid
+#[condition(pre="x: u32 < 100u32", post="(old(x: u32) * 2u32) == return: u32")]
+fn valid_old_reassigned(mut x: u32) -> u32 {
+    x = x + x;
+    x
+}
+
+// The argument is changed before it is returned
+// Should be invalid
+#[condition(pre="x: u32 < 100u32", post="return: u32 == old(x: u32)")]
+fn invalid_old_changed(mut x: u32) -> u32 {
+    x = x + 1;
+    x
+}
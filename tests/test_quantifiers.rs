@@ -0,0 +1,37 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Quantifier Tests
+// * * *
+
+// Every value below x is below 10, so x is at most 10
+// Should be valid
+#[condition(pre="forall(i: u32, (i: u32 < x: u32) => (i: u32 < 10u32))", post="return: u32 <= 10u32")]
+fn valid_forall_precondition(x: u32) -> u32 {
+    x
+}
+
+// Every value has a predecessor, with wrapping
+// Should be valid
+#[condition(pre="true", post="exists(i: u32, (i: u32 + 1u32) == return: u32)")]
+fn valid_exists_postcondition(x: u32) -> u32 {
+    x
+}
+
+// Nothing is greater than u32::MAX
+// Should be invalid
+#[condition(pre="true", post="exists(i: u32, i: u32 > return: u32)")]
+fn invalid_exists_postcondition(x: u32) -> u32 {
+    x
+}
+
+// The bound variable shadows the argument of the same name
+// Should be invalid
+#[condition(pre="x: u32 < 10u32", post="forall(x: u32, x: u32 < 10u32)")]
+fn invalid_forall_shadowed(x: u32) -> u32 {
+    x
+}
@@ -0,0 +1,15 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// A recursive call is reported as an error at the call
+#[condition(pre="n: u32 <= 10u32", post="return: u32 == n: u32")]
+fn valid_count_down(n: u32) -> u32 {
+    if n == 0 {
+        0
+    } else {
+        valid_count_down(n - 1) + 1
+    }
+}
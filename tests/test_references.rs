@@ -0,0 +1,104 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[condition(pre="r: u32 < 100u32", post="r: u32 == old(r: u32) + 1u32")]
+fn increment(r: &mut u32) {
+    *r += 1;
+}
+
+// * * *
+// Shared Reference Tests
+// * * *
+
+// Should be valid
+#[condition(pre="x: u32 < 100u32", post="return: u32 == x: u32 + 1u32")]
+fn valid_shared_argument(x: &u32) -> u32 {
+    *x + 1
+}
+
+// Should be valid
+#[condition(pre="p.x: i32 < 100i32", post="return: i32 == p.x: i32 + 1i32")]
+fn valid_shared_struct(p: &Point) -> i32 {
+    p.x + 1
+}
+
+// Should be valid
+#[condition(pre="true", post="return: u32 == x: u32")]
+fn valid_local_borrow(x: u32) -> u32 {
+    let r = &x;
+    *r
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: u32 == x: u32 + 1u32")]
+fn invalid_shared_argument(x: &u32) -> u32 {
+    *x + 1
+}
+
+// * * *
+// Mutable Reference Tests
+// * * *
+
+// Should be valid
+#[condition(pre="true", post="r: &mut u32 == 5u32")]
+fn valid_write_argument(r: &mut u32) {
+    *r = 5;
+}
+
+// Should be valid
+#[condition(pre="r: u32 < 100u32", post="r: u32 == old(r: u32) + 1u32")]
+fn valid_increment_argument(r: &mut u32) {
+    *r += 1;
+}
+
+// Should be valid
+#[condition(pre="true", post="return: u32 == 5u32")]
+fn valid_write_local(x: u32) -> u32 {
+    let mut y = x;
+    {
+        let r = &mut y;
+        *r = 5;
+    }
+    y
+}
+
+// Should be valid
+#[condition(pre="x: u32 < 50u32", post="return: u32 == x: u32 + 1u32")]
+fn valid_call_with_mutable_borrow(x: u32) -> u32 {
+    let mut y = x;
+    increment(&mut y);
+    y
+}
+
+// Should be invalid
+#[condition(pre="true", post="r: u32 == old(r: u32)")]
+fn invalid_write_argument(r: &mut u32) {
+    *r = 5;
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: u32 == x: u32")]
+fn invalid_write_local(x: u32) -> u32 {
+    let mut y = x;
+    {
+        let r = &mut y;
+        *r = 5;
+    }
+    y
+}
+
+// Should be invalid
+#[condition(pre="x: u32 < 50u32", post="return: u32 == x: u32")]
+fn invalid_call_with_mutable_borrow(x: u32) -> u32 {
+    let mut y = x;
+    increment(&mut y);
+    y
+}
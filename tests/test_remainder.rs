@@ -0,0 +1,46 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Remainder and Negation Tests
+// * * *
+
+// Should be valid
+#[condition(pre="(x: i32 < 0i32) && (x: i32 > -10i32)", post="return: i32 <= 0i32")]
+fn valid_rem_sign_follows_dividend(x: i32) -> i32 {
+    x % 5
+}
+
+// Should be valid
+#[condition(pre="true", post="return: i32 == -1i32")]
+fn valid_rem_negative_constant() -> i32 {
+    let x = -7;
+    x % 2
+}
+
+// Should be valid
+#[condition(pre="(y: i32 != 0i32) && (y: i32 != -1i32)", post="true")]
+fn valid_rem_not_overflowing(x: i32, y: i32) -> i32 {
+    x % y
+}
+
+// Should be valid
+#[condition(pre="x: i64 > -9223372036854775808i64", post="return: i64 == 0i64 - x: i64")]
+fn valid_negate_not_minimum(x: i64) -> i64 {
+    -x
+}
+
+// Should be invalid
+#[condition(pre="y: i32 != 0i32", post="true")]
+fn invalid_rem_minimum_by_negative_one(x: i32, y: i32) -> i32 {
+    x % y
+}
+
+// Should be invalid
+#[condition(pre="true", post="true")]
+fn invalid_negate_minimum(x: i8) -> i8 {
+    -x
+}
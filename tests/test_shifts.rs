@@ -0,0 +1,39 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Shift Tests
+// * * *
+
+// Should be valid
+#[condition(pre="n: u32 < 8u32", post="return: u8 == (x: u8 << n: u32)")]
+fn valid_shift_left_in_range(x: u8, n: u32) -> u8 {
+    x << n
+}
+
+// Should be valid
+#[condition(pre="n: u8 < 64u8", post="return: i64 == (x: i64 >> n: u8)")]
+fn valid_shift_right_mixed_types(x: i64, n: u8) -> i64 {
+    x >> n
+}
+
+// Should be valid
+#[condition(pre="true", post="return: u32 == (x: u32 >> 31u32)")]
+fn valid_shift_by_constant(x: u32) -> u32 {
+    x >> 31
+}
+
+// Should be invalid
+#[condition(pre="n: u32 <= 8u32", post="true")]
+fn invalid_shift_left_by_width(x: u8, n: u32) -> u8 {
+    x << n
+}
+
+// Should be invalid
+#[condition(pre="true", post="true")]
+fn invalid_shift_right_negative_amount(x: i32, n: i8) -> i32 {
+    x >> n
+}
@@ -0,0 +1,62 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Rect {
+    min: Point,
+    max: Point,
+}
+
+// * * *
+// Struct Field Tests
+// * * *
+
+// Should be valid
+#[condition(pre="p.x: i32 < 100i32", post="return: i32 == p.x: i32 + 1i32")]
+fn valid_field_read(p: Point) -> i32 {
+    p.x + 1
+}
+
+// Should be valid
+#[condition(pre="true", post="(return.x: i32 == y: i32) AND (return.y: i32 == x: i32)")]
+fn valid_struct_construct(x: i32, y: i32) -> Point {
+    Point { x: y, y: x }
+}
+
+// Should be valid
+#[condition(pre="r.min.x: i32 <= r.max.x: i32", post="return: bool == true")]
+fn valid_nested_field(r: Rect) -> bool {
+    r.min.x <= r.max.x
+}
+
+// Should be valid
+#[condition(pre="true", post="(return.min.x: i32 == 0i32) AND (return.max.y: i32 == p.y: i32)")]
+fn valid_nested_construct(p: Point) -> Rect {
+    let origin = Point { x: 0, y: 0 };
+    Rect { min: origin, max: p }
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: i32 == p.x: i32 + 1i32")]
+fn invalid_field_read_overflow(p: Point) -> i32 {
+    p.x + 1
+}
+
+// Should be invalid
+#[condition(pre="true", post="return.x: i32 == x: i32")]
+fn invalid_struct_construct(x: i32, y: i32) -> Point {
+    Point { x: y, y: x }
+}
+
+// Should be invalid
+#[condition(pre="true", post="return: bool == true")]
+fn invalid_nested_field(r: Rect) -> bool {
+    r.min.x <= r.max.x
+}
@@ -0,0 +1,47 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// Tuple Tests
+// * * *
+
+// Should be valid
+#[condition(pre="y: u32 > 0u32",
+            post="(return.0: u32 * y: u32 + return.1: u32 == x: u32) AND (return.1: u32 < y: u32)")]
+fn valid_div_rem(x: u32, y: u32) -> (u32, u32) {
+    (x / y, x % y)
+}
+
+// Should be valid
+#[condition(pre="true", post="(return.0: u32 == x: u32) AND (return.1: bool == true)")]
+fn valid_tuple_variable(x: u32) -> (u32, bool) {
+    let pair = (x, true);
+    pair
+}
+
+// Should be valid
+#[condition(pre="true", post="return: u32 == pair.1: u32")]
+fn valid_tuple_argument(pair: (u32, u32)) -> u32 {
+    pair.1
+}
+
+// Should be valid
+#[condition(pre="true", post="(return.0: i32 == pair.1: i32) AND (return.1: i32 == pair.0: i32)")]
+fn valid_tuple_swap(pair: (i32, i32)) -> (i32, i32) {
+    (pair.1, pair.0)
+}
+
+// Should be invalid
+#[condition(pre="y: u32 > 0u32", post="return.1: u32 == 0u32")]
+fn invalid_div_rem(x: u32, y: u32) -> (u32, u32) {
+    (x / y, x % y)
+}
+
+// Should be invalid
+#[condition(pre="true", post="return.0: i32 == pair.0: i32")]
+fn invalid_tuple_swap(pair: (i32, i32)) -> (i32, i32) {
+    (pair.1, pair.0)
+}
@@ -0,0 +1,37 @@
+#![feature(plugin, custom_attribute)]
+#![plugin(rustproof)]
+#![allow(dead_code)]
+#![allow(unused_attributes)]
+fn main() { }
+
+// * * *
+// isize and usize Tests
+// * * *
+
+// Adding one to a usize below its maximum does not overflow
+// Should be valid
+#[condition(pre="x: usize < usize::MAX", post="return: usize > x: usize")]
+fn valid_usize_add(x: usize) -> usize {
+    x + 1
+}
+
+// Negating an isize above its minimum does not overflow
+// Should be valid
+#[condition(pre="x: isize > isize::MIN", post="return: isize == (0isize - x: isize)")]
+fn valid_isize_neg(x: isize) -> isize {
+    -x
+}
+
+// usize::MAX + 1 overflows
+// Should be invalid
+#[condition(pre="true", post="return: usize > x: usize")]
+fn invalid_usize_add(x: usize) -> usize {
+    x + 1
+}
+
+// Subtracting from a usize can go below zero
+// Should be invalid
+#[condition(pre="x: usize < 10usize", post="true")]
+fn invalid_usize_sub(x: usize) -> usize {
+    x - 1
+}